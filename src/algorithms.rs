@@ -1,28 +1,32 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
-use crate::{Class, ClassId, EGraph, Node, NodeId};
+use indexmap::IndexMap;
+
+use crate::{Class, ClassId, Cost, EGraph, Error, Node, NodeId};
 
 pub const MISSING_ARG_VALUE: &str = "·";
 
+/// What was removed by [`EGraph::retain_reachable`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RetainStats {
+    pub removed_nodes: usize,
+    pub removed_classes: usize,
+    pub removed_class_data: usize,
+}
+
 impl EGraph {
     /// Inline all leaves (e-classes with a single node that has no children) into their parents, so that they
     /// are added to the function name like f(10, ·).
     /// Returns the number of leaves inlined.
     pub fn inline_leaves(&mut self) -> usize {
-        // 1. Create mapping of eclass to nodes as well as nodes to their parents
+        self.refresh_caches();
+        // 1. Create mapping of eclass to nodes
         let mut eclass_to_nodes = std::collections::HashMap::new();
-        let mut node_to_parents = std::collections::HashMap::new();
         for (node_id, node) in &self.nodes {
             eclass_to_nodes
                 .entry(node.eclass.clone())
                 .or_insert_with(Vec::new)
                 .push((node_id.clone(), node));
-            for child in &node.children {
-                node_to_parents
-                    .entry(child.clone())
-                    .or_insert_with(Vec::new)
-                    .push(node_id.clone());
-            }
         }
         // 2. Find all leaves (e-classes with a single node that has no children and also not in root-eclasses)
         let mut leaves = Vec::new();
@@ -34,17 +38,16 @@ impl EGraph {
             }
         }
         // 3. Create mapping from all parents which are updated to the children which are inlined
+        // Leaves are alone in their e-class, so the parents of the e-class are the parents of the leaf.
+        // There will be no parents for isolated nodes with no parents or children, and those aren't inlined.
         let mut parents_to_children = std::collections::HashMap::new();
-        for (_, node_id) in &leaves {
-            let parents = node_to_parents.get(node_id);
-            // There will be no parents for isolated nodes with no parents or children
-            if let Some(parents) = parents {
-                for parent in parents {
-                    parents_to_children
-                        .entry(parent.clone())
-                        .or_insert_with(Vec::new)
-                        .push(node_id.clone());
-                }
+        leaves.retain(|(eclass, _)| !self.class_parents(eclass).is_empty());
+        for (eclass, node_id) in &leaves {
+            for (parent, _) in self.class_parents(eclass) {
+                parents_to_children
+                    .entry(parent.clone())
+                    .or_insert_with(Vec::new)
+                    .push(node_id.clone());
             }
         }
         // 4. Inline leaf nodes into their parents
@@ -92,18 +95,14 @@ impl EGraph {
             parent_node.op = new_op;
             parent_node.cost += additional_cost;
         }
-        let mut n_inlined = 0;
-        // 5. Remove leaf nodes from egraph, class data, and root eclasses
+        // 5. Remove inlined leaf nodes from egraph, class data, and root eclasses
+        let n_inlined = leaves.len();
         for (eclass, node_id) in &leaves {
-            // If this node has no parents, don't remove it, since it wasn't inlined
-            if !node_to_parents.contains_key(node_id) {
-                continue;
-            }
-            n_inlined += 1;
             self.nodes.swap_remove(node_id);
             self.class_data.swap_remove(eclass);
             self.root_eclasses.retain(|root| root != eclass);
         }
+        self.invalidate_caches();
         n_inlined
     }
 
@@ -112,6 +111,151 @@ impl EGraph {
         while self.inline_leaves() > 0 {}
     }
 
+    /// Removes all nodes and class data of e-classes that can't be reached from `root_eclasses` by following
+    /// children, returning how much was removed.
+    ///
+    /// If there are no root e-classes, everything is removed.
+    pub fn retain_reachable(&mut self) -> RetainStats {
+        self.refresh_caches();
+        let reachable = self.reachable_classes(&self.root_eclasses);
+        let n_nodes = self.nodes.len();
+        let n_classes = self.classes().len();
+        let n_class_data = self.class_data.len();
+        self.nodes
+            .retain(|_, node| reachable.contains(&node.eclass));
+        self.class_data
+            .retain(|class_id, _| reachable.contains(class_id));
+        self.invalidate_caches();
+        RetainStats {
+            removed_nodes: n_nodes - self.nodes.len(),
+            removed_classes: n_classes - self.classes().len(),
+            removed_class_data: n_class_data - self.class_data.len(),
+        }
+    }
+
+    /// All e-classes reachable from `roots` by following the children of their nodes, including the roots
+    /// themselves.
+    ///
+    /// Children that are not in the e-graph are ignored.
+    pub(crate) fn reachable_classes(&self, roots: &[ClassId]) -> HashSet<ClassId> {
+        let mut reachable = HashSet::new();
+        let mut todo = roots.to_vec();
+        while let Some(class_id) = todo.pop() {
+            if !reachable.insert(class_id.clone()) {
+                continue;
+            }
+            let Some(class) = self.classes().get(&class_id) else {
+                continue;
+            };
+            for node_id in &class.nodes {
+                for child in &self.nodes[node_id].children {
+                    if let Some(child) = self.nodes.get(child) {
+                        if !reachable.contains(&child.eclass) {
+                            todo.push(child.eclass.clone());
+                        }
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Returns a new e-graph with only the e-classes within `depth` child hops of `roots`.
+    ///
+    /// See [`EGraph::subgraph_with_parents`] for details.
+    pub fn subgraph(&self, roots: &[ClassId], depth: usize) -> EGraph {
+        self.subgraph_with_parents(roots, depth, 0)
+    }
+
+    /// Returns a new e-graph with only the e-classes within `depth` child hops or `parent_depth` parent hops of
+    /// `roots`, which become the root e-classes of the result.
+    ///
+    /// All nodes and class data of the kept e-classes are copied. Each e-class outside the slice that a kept node
+    /// points to becomes a stub: its class data is copied, but it holds a single subsumed leaf node with op
+    /// [`MISSING_ARG_VALUE`] and no cost, so nodes at the edge of the slice keep all of their children.
+    /// Stub nodes come after the kept nodes and are named after their e-class, like `X·` for the e-class `X`.
+    pub fn subgraph_with_parents(
+        &self,
+        roots: &[ClassId],
+        depth: usize,
+        parent_depth: usize,
+    ) -> EGraph {
+        let classes = self.classes();
+        let child_classes = |class_id: &ClassId| -> Vec<ClassId> {
+            classes[class_id]
+                .nodes
+                .iter()
+                .flat_map(|node_id| &self.nodes[node_id].children)
+                .filter_map(|child| Some(self.nodes.get(child)?.eclass.clone()))
+                .collect()
+        };
+
+        let roots: Vec<ClassId> = roots
+            .iter()
+            .filter(|root| classes.contains_key(*root))
+            .cloned()
+            .collect();
+        let mut kept = neighborhood(&roots, depth, child_classes);
+        kept.extend(neighborhood(&roots, parent_depth, |class_id| {
+            self.class_parents(class_id)
+                .iter()
+                .map(|(parent, _)| self.nodes[parent].eclass.clone())
+                .collect()
+        }));
+
+        let mut egraph = EGraph {
+            root_eclasses: roots,
+            ..Default::default()
+        };
+        let mut stubs: IndexMap<ClassId, NodeId> = IndexMap::new();
+        let mut stub_ids: HashSet<NodeId> = HashSet::new();
+        for (node_id, node) in &self.nodes {
+            if !kept.contains(&node.eclass) {
+                continue;
+            }
+            let mut node = node.clone();
+            for child in &mut node.children {
+                let Some(child_class) = self.nodes.get(child).map(|child| &child.eclass) else {
+                    continue;
+                };
+                if !kept.contains(child_class) {
+                    if !stubs.contains_key(child_class) {
+                        let stub_id = self.stub_id(child_class, &stub_ids);
+                        stub_ids.insert(stub_id.clone());
+                        stubs.insert(child_class.clone(), stub_id);
+                    }
+                    *child = stubs[child_class].clone();
+                }
+            }
+            egraph.nodes.insert(node_id.clone(), node);
+        }
+        for (class_id, stub_id) in stubs {
+            let stub = Node {
+                op: MISSING_ARG_VALUE.to_string(),
+                children: vec![],
+                eclass: class_id,
+                cost: Cost::default(),
+                subsumed: true,
+            };
+            egraph.nodes.insert(stub_id, stub);
+        }
+        for (class_id, data) in &self.class_data {
+            if egraph.classes().contains_key(class_id) {
+                egraph.class_data.insert(class_id.clone(), data.clone());
+            }
+        }
+        egraph
+    }
+
+    /// A node id for the stub of `class_id` in a slice, which is not used by any node in the e-graph or in `taken`
+    fn stub_id(&self, class_id: &ClassId, taken: &HashSet<NodeId>) -> NodeId {
+        let mut id = NodeId::from(format!("{class_id}{MISSING_ARG_VALUE}"));
+        while self.nodes.contains_key(&id) || taken.contains(&id) {
+            id = format!("{id}{MISSING_ARG_VALUE}").into();
+        }
+        id
+    }
+
     /// Given some function `should_split`, after calling this method, all nodes where it is true will have at most
     /// one other node in their e-class and if they have parents, will no other nodes in their e-class.
     ///
@@ -132,34 +276,46 @@ impl EGraph {
     /// Another way to think about it is that any isomporphic function can be split, since if f(a) = f(b) then a = b,
     /// in that case.
     pub fn split_classes(&mut self, should_split: impl Fn(&NodeId, &Node) -> bool) {
+        if let Err(e) = self.try_split_classes(should_split) {
+            panic!("{e}");
+        }
+    }
+
+    /// Like [`EGraph::split_classes`], but returns an error instead of panicking if `should_split` is true for
+    /// multiple nodes in an e-class, or if a node has a child that is not in the e-graph.
+    ///
+    /// Each pass checks every e-class before modifying anything, so an error in the first pass leaves the e-graph
+    /// unchanged.
+    pub fn try_split_classes(
+        &mut self,
+        should_split: impl Fn(&NodeId, &Node) -> bool,
+    ) -> Result<(), Error> {
+        self.refresh_caches();
         // run till fixpoint since splitting a node might add more parents and require splitting the child down the line
         let mut changed = true;
         while changed {
             changed = false;
+            for child in self.nodes.values().flat_map(|node| &node.children) {
+                self.get_node(child)?;
+            }
             // Mapping from class ID to all nodes that point to any node in that e-class
-            let parents: HashMap<ClassId, Vec<(NodeId, usize)>> =
-                self.nodes
-                    .iter()
-                    .fold(HashMap::new(), |mut parents, (node_id, node)| {
-                        for (position, child) in node.children.iter().enumerate() {
-                            let child_class = self.nodes[child].eclass.clone();
-                            parents
-                                .entry(child_class)
-                                .or_default()
-                                .push((node_id.clone(), position));
-                        }
-                        parents
-                    });
+            let parents = self.parents().clone();
 
+            let mut splits = vec![];
             for Class { id, nodes } in self.classes().clone().into_values() {
                 let (unique_nodes, other_nodes): (Vec<_>, Vec<_>) = nodes
                     .into_iter()
                     .partition(|node_id| should_split(node_id, &self.nodes[node_id]));
                 if unique_nodes.len() > 1 {
-                    panic!(
-                        "Multiple nodes in one e-class should be split. E-class: {id:?} Nodes: {unique_nodes:?}"
-                    );
+                    return Err(Error::MultipleSplitNodes {
+                        class: id,
+                        nodes: unique_nodes,
+                    });
                 }
+                splits.push((id, unique_nodes, other_nodes));
+            }
+
+            for (id, unique_nodes, other_nodes) in splits {
                 let unique_node = unique_nodes.into_iter().next();
                 let class_data = self.class_data.get(&id).cloned();
                 if let Some(unique_node_id) = unique_node {
@@ -214,7 +370,30 @@ impl EGraph {
                 }
             }
             // reset the classes computation
-            self.once_cell_classes.take();
+            self.invalidate_caches();
+        }
+        Ok(())
+    }
+}
+
+/// All e-classes within `depth` hops of `roots`, where `next` gives the e-classes one hop away
+fn neighborhood(
+    roots: &[ClassId],
+    depth: usize,
+    next: impl Fn(&ClassId) -> Vec<ClassId>,
+) -> HashSet<ClassId> {
+    let mut seen: HashSet<ClassId> = roots.iter().cloned().collect();
+    let mut frontier = roots.to_vec();
+    for _ in 0..depth {
+        let mut next_frontier = vec![];
+        for class_id in &frontier {
+            for next_class in next(class_id) {
+                if seen.insert(next_class.clone()) {
+                    next_frontier.push(next_class);
+                }
+            }
         }
+        frontier = next_frontier;
     }
+    seen
 }
@@ -0,0 +1,217 @@
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::Arc;
+
+use indexmap::IndexSet;
+
+use crate::{ClassData, ClassId, Cost, EGraph, Node, NodeId};
+
+const MAGIC: &[u8; 4] = b"EGSB";
+const VERSION: u64 = 1;
+
+impl EGraph {
+    /// Writes the e-graph in a compact binary format
+    ///
+    /// All strings (ids, ops, types and class data) are stored once in a table up front and then referred
+    /// to by their index, and all integers are LEB128 varints. The layout is:
+    ///
+    /// ```text
+    /// magic "EGSB", version
+    /// strings:    count, then (length, utf-8 bytes) for each
+    /// nodes:      count, then (id, op, eclass, cost as f64 LE, subsumed as u8, n children, children..) for each
+    /// roots:      count, then class id for each
+    /// class data: count, then (class id, type index + 1 or 0 for none, n extra, (key, value)..) for each
+    /// ```
+    pub fn to_binary_writer(&self, writer: impl Write) -> io::Result<()> {
+        let mut strings: IndexSet<&str> = IndexSet::new();
+        for (node_id, node) in &self.nodes {
+            strings.insert(node_id.as_ref());
+            strings.insert(&node.op);
+            strings.insert(node.eclass.as_ref());
+            strings.extend(node.children.iter().map(|child| child.as_ref()));
+        }
+        strings.extend(self.root_eclasses.iter().map(|root| root.as_ref()));
+        for (class_id, data) in &self.class_data {
+            strings.insert(class_id.as_ref());
+            strings.extend(data.typ.as_deref());
+            for (key, value) in &data.extra {
+                strings.insert(key);
+                strings.insert(value);
+            }
+        }
+        let index = |s: &str| strings.get_index_of(s).unwrap() as u64;
+
+        let mut w = BufWriter::new(writer);
+        w.write_all(MAGIC)?;
+        write_varint(&mut w, VERSION)?;
+
+        write_varint(&mut w, strings.len() as u64)?;
+        for s in &strings {
+            write_varint(&mut w, s.len() as u64)?;
+            w.write_all(s.as_bytes())?;
+        }
+
+        write_varint(&mut w, self.nodes.len() as u64)?;
+        for (node_id, node) in &self.nodes {
+            write_varint(&mut w, index(node_id.as_ref()))?;
+            write_varint(&mut w, index(&node.op))?;
+            write_varint(&mut w, index(node.eclass.as_ref()))?;
+            w.write_all(&node.cost.into_inner().to_le_bytes())?;
+            w.write_all(&[node.subsumed as u8])?;
+            write_varint(&mut w, node.children.len() as u64)?;
+            for child in &node.children {
+                write_varint(&mut w, index(child.as_ref()))?;
+            }
+        }
+
+        write_varint(&mut w, self.root_eclasses.len() as u64)?;
+        for root in &self.root_eclasses {
+            write_varint(&mut w, index(root.as_ref()))?;
+        }
+
+        write_varint(&mut w, self.class_data.len() as u64)?;
+        for (class_id, data) in &self.class_data {
+            write_varint(&mut w, index(class_id.as_ref()))?;
+            write_varint(&mut w, data.typ.as_deref().map_or(0, |typ| index(typ) + 1))?;
+            write_varint(&mut w, data.extra.len() as u64)?;
+            for (key, value) in &data.extra {
+                write_varint(&mut w, index(key))?;
+                write_varint(&mut w, index(value))?;
+            }
+        }
+        w.flush()
+    }
+
+    /// Reads an e-graph written by [`EGraph::to_binary_writer`]
+    pub fn from_binary_reader(reader: impl Read) -> io::Result<Self> {
+        let mut r = BufReader::new(reader);
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a binary e-graph file"));
+        }
+        let version = read_varint(&mut r)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported binary e-graph version {version}, expected {VERSION}"
+            )));
+        }
+
+        let n_strings = read_varint(&mut r)?;
+        let mut strings: Vec<Arc<str>> = vec![];
+        for _ in 0..n_strings {
+            let len = read_varint(&mut r)?;
+            let mut bytes = vec![];
+            r.by_ref().take(len).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let s = String::from_utf8(bytes).map_err(invalid_data)?;
+            strings.push(s.into());
+        }
+        let mut egraph = EGraph::default();
+        let n_nodes = read_varint(&mut r)?;
+        for _ in 0..n_nodes {
+            let node_id = NodeId(read_string(&mut r, &strings)?);
+            let op = read_string(&mut r, &strings)?.to_string();
+            let eclass = ClassId(read_string(&mut r, &strings)?);
+            let mut cost = [0; 8];
+            r.read_exact(&mut cost)?;
+            let cost = Cost::new(f64::from_le_bytes(cost)).map_err(invalid_data)?;
+            let mut subsumed = [0];
+            r.read_exact(&mut subsumed)?;
+            let n_children = read_varint(&mut r)?;
+            let children = (0..n_children)
+                .map(|_| Ok(NodeId(read_string(&mut r, &strings)?)))
+                .collect::<io::Result<_>>()?;
+            let node = Node {
+                op,
+                children,
+                eclass,
+                cost,
+                subsumed: subsumed[0] != 0,
+            };
+            egraph.try_add_node(node_id, node).map_err(invalid_data)?;
+        }
+
+        let n_roots = read_varint(&mut r)?;
+        for _ in 0..n_roots {
+            egraph
+                .root_eclasses
+                .push(ClassId(read_string(&mut r, &strings)?));
+        }
+
+        let n_class_data = read_varint(&mut r)?;
+        for _ in 0..n_class_data {
+            let class_id = ClassId(read_string(&mut r, &strings)?);
+            let typ = match read_varint(&mut r)? {
+                0 => None,
+                i => Some(lookup_string(&strings, i - 1)?.to_string()),
+            };
+            let n_extra = read_varint(&mut r)?;
+            let extra = (0..n_extra)
+                .map(|_| {
+                    Ok((
+                        read_string(&mut r, &strings)?.to_string(),
+                        read_string(&mut r, &strings)?.to_string(),
+                    ))
+                })
+                .collect::<io::Result<_>>()?;
+            egraph.class_data.insert(class_id, ClassData { typ, extra });
+        }
+        Ok(egraph)
+    }
+
+    pub fn from_binary_file(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        Self::from_binary_reader(std::fs::File::open(path)?)
+    }
+
+    pub fn to_binary_file(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        self.to_binary_writer(std::fs::File::create(path)?)
+    }
+
+    pub fn test_binary_round_trip(&self) {
+        let mut bytes = vec![];
+        self.to_binary_writer(&mut bytes).unwrap();
+        let egraph2 = EGraph::from_binary_reader(bytes.as_slice()).unwrap();
+        assert_eq!(self, &egraph2);
+    }
+}
+
+fn read_string(r: &mut impl Read, strings: &[Arc<str>]) -> io::Result<Arc<str>> {
+    lookup_string(strings, read_varint(r)?)
+}
+
+fn lookup_string(strings: &[Arc<str>], i: u64) -> io::Result<Arc<str>> {
+    strings
+        .get(i as usize)
+        .cloned()
+        .ok_or_else(|| invalid_data(format!("string index {i} out of range")))
+}
+
+fn invalid_data(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn write_varint(w: &mut impl Write, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(r: &mut impl Read) -> io::Result<u64> {
+    let mut n = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        n |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(invalid_data("varint is too long"))
+}
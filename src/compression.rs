@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Opens a JSON file for reading, decompressing it if it starts with the gzip or zstd magic bytes
+pub(crate) fn open_json_file(path: impl AsRef<Path>) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = reader.fill_buf()?;
+    if header.starts_with(GZIP_MAGIC) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )));
+        #[cfg(not(feature = "gzip"))]
+        return Err(missing_feature("gzip"));
+    }
+    if header.starts_with(ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)));
+        #[cfg(not(feature = "zstd"))]
+        return Err(missing_feature("zstd"));
+    }
+    Ok(Box::new(reader))
+}
+
+/// Writes `value` as pretty printed JSON, compressed with gzip if the path ends in `.gz` or with zstd if it
+/// ends in `.zst`
+pub(crate) fn write_json_file(
+    path: impl AsRef<Path>,
+    value: &impl serde::Serialize,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    // Check the features before creating the file, so an existing file is left alone on failure
+    #[cfg(not(feature = "gzip"))]
+    if extension == Some("gz") {
+        return Err(missing_feature("gzip"));
+    }
+    #[cfg(not(feature = "zstd"))]
+    if extension == Some("zst") {
+        return Err(missing_feature("zstd"));
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    match extension {
+        #[cfg(feature = "gzip")]
+        Some("gz") => {
+            let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            serde_json::to_writer_pretty(&mut encoder, value)?;
+            encoder.finish()?.flush()
+        }
+        #[cfg(feature = "zstd")]
+        Some("zst") => {
+            let mut encoder = zstd::Encoder::new(writer, 0)?;
+            serde_json::to_writer_pretty(&mut encoder, value)?;
+            encoder.finish()?.flush()
+        }
+        _ => {
+            let mut writer = writer;
+            serde_json::to_writer_pretty(&mut writer, value)?;
+            writer.flush()
+        }
+    }
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn missing_feature(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{feature} compressed files need the `{feature}` feature of egraph-serialize"),
+    )
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use crate::{ClassId, EGraph, NodeId};
+
+/// Cycles in the e-class graph, where each e-class points to the e-classes of its nodes' children
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CycleReport {
+    /// Strongly connected components that contain a cycle: those with more than one e-class, or a single
+    /// e-class with a self-loop
+    pub cyclic_components: Vec<Vec<ClassId>>,
+    /// E-classes with a node that has a child in the same e-class
+    pub self_loops: Vec<ClassId>,
+    /// Nodes with a child in the same cyclic component as the node itself
+    pub cycle_nodes: Vec<NodeId>,
+}
+
+impl CycleReport {
+    pub fn is_acyclic(&self) -> bool {
+        self.cyclic_components.is_empty()
+    }
+
+    /// All e-classes that are part of some cycle
+    pub fn cyclic_classes(&self) -> impl Iterator<Item = &ClassId> {
+        self.cyclic_components.iter().flatten()
+    }
+}
+
+impl EGraph {
+    /// Computes the strongly connected components of the e-class graph, where each e-class points to the
+    /// e-classes of its nodes' children.
+    ///
+    /// Components are returned children first, so no component points to one that comes after it.
+    /// Children that are not in the e-graph are ignored.
+    pub fn class_sccs(&self) -> Vec<Vec<ClassId>> {
+        let classes = self.classes();
+        let successors: Vec<Vec<usize>> = classes
+            .values()
+            .map(|class| {
+                let mut succ: Vec<usize> = class
+                    .nodes
+                    .iter()
+                    .flat_map(|node_id| &self.nodes[node_id].children)
+                    .filter_map(|child| classes.get_index_of(&self.nodes.get(child)?.eclass))
+                    .collect();
+                succ.sort_unstable();
+                succ.dedup();
+                succ
+            })
+            .collect();
+
+        // Tarjan's algorithm, with an explicit call stack so deep e-graphs don't overflow
+        let n = successors.len();
+        let mut index = vec![usize::MAX; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = vec![];
+        let mut next_index = 0;
+        let mut components = vec![];
+        for start in 0..n {
+            if index[start] != usize::MAX {
+                continue;
+            }
+            // Each frame is a class and how many of its successors have been visited
+            let mut calls = vec![(start, 0)];
+            index[start] = next_index;
+            lowlink[start] = next_index;
+            next_index += 1;
+            stack.push(start);
+            on_stack[start] = true;
+            while let Some(&mut (v, ref mut i)) = calls.last_mut() {
+                if let Some(&w) = successors[v].get(*i) {
+                    *i += 1;
+                    if index[w] == usize::MAX {
+                        index[w] = next_index;
+                        lowlink[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        calls.push((w, 0));
+                    } else if on_stack[w] {
+                        lowlink[v] = lowlink[v].min(index[w]);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[v]);
+                }
+                if lowlink[v] == index[v] {
+                    let mut component = vec![];
+                    loop {
+                        let w = stack.pop().unwrap();
+                        on_stack[w] = false;
+                        component.push(classes.get_index(w).unwrap().0.clone());
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.reverse();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Finds the cycles in the e-class graph, see [`CycleReport`]
+    pub fn cycles(&self) -> CycleReport {
+        let mut self_loops = vec![];
+        for (class_id, class) in self.classes() {
+            let has_self_loop = class.nodes.iter().any(|node_id| {
+                self.nodes[node_id]
+                    .children
+                    .iter()
+                    .any(|child| self.nodes.get(child).map(|c| &c.eclass) == Some(class_id))
+            });
+            if has_self_loop {
+                self_loops.push(class_id.clone());
+            }
+        }
+
+        let looping: HashSet<&ClassId> = self_loops.iter().collect();
+        let cyclic_components: Vec<Vec<ClassId>> = self
+            .class_sccs()
+            .into_iter()
+            .filter(|component| component.len() > 1 || looping.contains(&component[0]))
+            .collect();
+
+        let mut cycle_nodes = vec![];
+        for component in &cyclic_components {
+            let members: HashSet<&ClassId> = component.iter().collect();
+            for class_id in component {
+                for node_id in &self[class_id].nodes {
+                    let in_cycle = self.nodes[node_id].children.iter().any(|child| {
+                        self.nodes
+                            .get(child)
+                            .is_some_and(|c| members.contains(&c.eclass))
+                    });
+                    if in_cycle {
+                        cycle_nodes.push(node_id.clone());
+                    }
+                }
+            }
+        }
+
+        CycleReport {
+            cyclic_components,
+            self_loops,
+            cycle_nodes,
+        }
+    }
+}
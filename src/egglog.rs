@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::{ClassId, EGraph, Error, Node};
+
+/// The sort given to e-classes without a `typ` in their class data
+pub const DEFAULT_SORT: &str = "Expr";
+
+/// Sorts built into egglog, whose e-classes are values written as literals
+const PRIMITIVE_SORTS: &[&str] = &["i64", "f64", "String", "bool", "Unit", "BigInt", "BigRat"];
+
+/// Names with a meaning of their own in egglog, which are never used for constructors
+const RESERVED: &[&str] = &[
+    "and",
+    "birewrite",
+    "check",
+    "constructor",
+    "datatype",
+    "delete",
+    "extract",
+    "fail",
+    "function",
+    "include",
+    "let",
+    "max",
+    "min",
+    "not",
+    "or",
+    "panic",
+    "pop",
+    "push",
+    "relation",
+    "rewrite",
+    "rule",
+    "ruleset",
+    "run",
+    "run-schedule",
+    "set",
+    "sort",
+    "subsume",
+    "union",
+    "xor",
+];
+
+/// The op, argument sorts and output sort of a constructor
+type Signature = (String, Vec<String>, String);
+
+/// Hands out valid egglog identifiers, never the same one twice
+#[derive(Default)]
+struct Names {
+    taken: HashSet<String>,
+}
+
+impl Names {
+    fn fresh(&mut self, raw: &str) -> String {
+        let is_ident = raw.starts_with(|c: char| c.is_ascii_alphabetic())
+            && raw
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        let base = if is_ident && !RESERVED.contains(&raw) && !PRIMITIVE_SORTS.contains(&raw) {
+            raw.to_string()
+        } else {
+            let mut name = "op_".to_string();
+            for c in raw.chars() {
+                match c {
+                    c if c.is_ascii_alphanumeric() || c == '_' => name.push(c),
+                    '+' => name.push_str("plus"),
+                    '-' => name.push_str("minus"),
+                    '*' => name.push_str("times"),
+                    '/' => name.push_str("div"),
+                    '=' => name.push_str("eq"),
+                    '<' => name.push_str("lt"),
+                    '>' => name.push_str("gt"),
+                    _ => name.push('_'),
+                }
+            }
+            name
+        };
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while !self.taken.insert(name.clone()) {
+            suffix += 1;
+            name = format!("{base}_{suffix}");
+        }
+        name
+    }
+}
+
+impl EGraph {
+    /// Writes the e-graph as an egglog program that rebuilds it.
+    ///
+    /// Each e-class gets the sort named by its class data's `typ`, or [`DEFAULT_SORT`] if it has none.
+    /// E-classes of egglog's built-in sorts, like `i64` or `String`, are values: their childless node is
+    /// written as a literal, and the other nodes in them become `set` statements on functions
+    /// (or plain facts on relations, for `Unit`).
+    /// The other sorts are declared with `sort`, and every distinct combination of op, argument sorts and
+    /// output sort becomes a constructor, named after the op when it is a valid identifier.
+    ///
+    /// The program binds one representative term per e-class with `let`, chosen like in
+    /// [`EGraph::extract_bottom_up`], then `union`s every other node into it and `subsume`s the subsumed
+    /// ones. Root e-classes are not recorded.
+    ///
+    /// Fails with [`Error::MissingChoice`] if an e-class has no finite term, or if an e-class of a built-in
+    /// sort has no childless node to use as its value.
+    pub fn to_egglog(&self) -> Result<String, Error> {
+        let sort = |class_id: &ClassId| -> &str {
+            self.class_data
+                .get(class_id)
+                .and_then(|data| data.typ.as_deref())
+                .unwrap_or(DEFAULT_SORT)
+        };
+        let is_primitive = |class_id: &ClassId| PRIMITIVE_SORTS.contains(&sort(class_id));
+
+        // Pick a representative for every e-class, using the value itself for built-in sorts
+        let mut representatives = self.bottom_up(true);
+        for (class_id, class) in self.classes() {
+            if is_primitive(class_id) {
+                match class
+                    .nodes
+                    .iter()
+                    .find(|node_id| self[*node_id].children.is_empty())
+                {
+                    Some(node_id) => representatives.choose(class_id.clone(), node_id.clone()),
+                    None => return Err(Error::MissingChoice(class_id.clone())),
+                }
+            }
+        }
+        let class_ids: Vec<ClassId> = self.classes().keys().cloned().collect();
+        let order = representatives.topological_order(self, &class_ids)?;
+
+        let mut names = Names::default();
+        let mut sorts: IndexMap<&str, String> = IndexMap::new();
+        let mut sort_names = Names::default();
+        for class_id in &class_ids {
+            let typ = sort(class_id);
+            if !is_primitive(class_id) && !sorts.contains_key(typ) {
+                sorts.insert(typ, sort_names.fresh(typ));
+            }
+        }
+        let sort_name = |class_id: &ClassId| -> String {
+            sorts
+                .get(sort(class_id))
+                .cloned()
+                .unwrap_or_else(|| sort(class_id).to_string())
+        };
+
+        let mut vars: HashMap<&ClassId, String> = HashMap::new();
+        for class_id in &order {
+            if !is_primitive(class_id) {
+                vars.insert(class_id, names.fresh(&format!("c{}", vars.len())));
+            }
+        }
+
+        // Declare a constructor for each op and signature, in the order they are first used
+        let signature = |node: &Node| -> Signature {
+            let args = node
+                .children
+                .iter()
+                .map(|child| sort_name(self.nid_to_cid(child)))
+                .collect();
+            (node.op.clone(), args, sort_name(&node.eclass))
+        };
+        let mut declarations: IndexMap<Signature, String> = IndexMap::new();
+        for node in self.nodes.values() {
+            if is_primitive(&node.eclass) && node.children.is_empty() {
+                continue;
+            }
+            let key = signature(node);
+            if !declarations.contains_key(&key) {
+                let name = names.fresh(&node.op);
+                declarations.insert(key, name);
+            }
+        }
+        let value = |class_id: &ClassId| &self[&representatives.choices[class_id]].op;
+
+        let term = |node: &Node| -> String {
+            let mut term = format!("({}", declarations[&signature(node)]);
+            for child in &node.children {
+                let child_class = self.nid_to_cid(child);
+                term.push(' ');
+                term.push_str(vars.get(child_class).unwrap_or_else(|| value(child_class)));
+            }
+            term.push(')');
+            term
+        };
+
+        let mut lines = vec![];
+        for name in sorts.values() {
+            lines.push(format!("(sort {name})"));
+        }
+        for ((_, args, out), name) in &declarations {
+            let args = args.join(" ");
+            lines.push(match out.as_str() {
+                "Unit" => format!("(relation {name} ({args}))"),
+                out if PRIMITIVE_SORTS.contains(&out) => {
+                    format!("(function {name} ({args}) {out} :no-merge)")
+                }
+                out => format!("(constructor {name} ({args}) {out})"),
+            });
+        }
+        for class_id in &order {
+            if let Some(var) = vars.get(class_id) {
+                let node = &self[&representatives.choices[*class_id]];
+                lines.push(format!("(let {var} {})", term(node)));
+            }
+        }
+        for (node_id, node) in &self.nodes {
+            if let Some(var) = vars.get(&node.eclass) {
+                if representatives.choices[&node.eclass] != *node_id {
+                    lines.push(format!("(union {var} {})", term(node)));
+                }
+                if node.subsumed {
+                    lines.push(format!("(subsume {})", term(node)));
+                }
+            } else if !node.children.is_empty() {
+                if sort(&node.eclass) == "Unit" {
+                    lines.push(term(node));
+                } else {
+                    lines.push(format!("(set {} {})", term(node), value(&node.eclass)));
+                }
+            }
+        }
+        lines.push(String::new());
+        Ok(lines.join("\n"))
+    }
+
+    /// Writes the program from [`EGraph::to_egglog`] to a file
+    pub fn to_egglog_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let program = self.to_egglog().map_err(std::io::Error::other)?;
+        std::fs::write(path, program)
+    }
+}
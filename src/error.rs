@@ -0,0 +1,71 @@
+use std::fmt;
+
+use crate::{ClassId, NodeId};
+
+/// Errors returned by the fallible methods on [`EGraph`](crate::EGraph).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A node with this id already exists.
+    DuplicateNode(NodeId),
+    /// There is no node with this id.
+    MissingNode(NodeId),
+    /// There is no e-class with this id.
+    MissingClass(ClassId),
+    /// More than one node in an e-class matched the predicate passed to
+    /// [`EGraph::try_split_classes`](crate::EGraph::try_split_classes).
+    MultipleSplitNodes { class: ClassId, nodes: Vec<NodeId> },
+    /// An extraction result has no node chosen for a reachable e-class.
+    MissingChoice(ClassId),
+    /// An extraction result chose a node for an e-class the node does not belong to.
+    WrongClass { class: ClassId, node: NodeId },
+    /// The chosen nodes form a cycle through this e-class.
+    Cycle(ClassId),
+    /// A node in a petgraph graph is not a member of exactly one e-class.
+    NodeClass(NodeId),
+    /// An edge in a petgraph graph connects the wrong kinds of vertices.
+    InvalidEdge { source: String, target: String },
+    /// S-expression input could not be parsed, at this byte offset.
+    Parse { position: usize, message: String },
+    /// There are more of `what` than fit in a `u32` index.
+    TooLarge { what: &'static str, count: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DuplicateNode(id) => write!(f, "duplicate node with id {id}"),
+            Error::MissingNode(id) => write!(f, "no node with id {id}"),
+            Error::MissingClass(id) => write!(f, "no class with id {id}"),
+            Error::MultipleSplitNodes { class, nodes } => {
+                write!(f, "multiple nodes in e-class {class} should be split:")?;
+                for node in nodes {
+                    write!(f, " {node}")?;
+                }
+                Ok(())
+            }
+            Error::MissingChoice(class) => write!(f, "no node chosen for e-class {class}"),
+            Error::WrongClass { class, node } => write!(
+                f,
+                "node {node} was chosen for e-class {class} but is not in it"
+            ),
+            Error::Cycle(class) => {
+                write!(f, "chosen nodes form a cycle through e-class {class}")
+            }
+            Error::NodeClass(id) => {
+                write!(f, "node {id} must be a member of exactly one e-class")
+            }
+            Error::InvalidEdge { source, target } => {
+                write!(f, "invalid edge from {source} to {target}")
+            }
+            Error::Parse { position, message } => {
+                write!(f, "parse error at byte {position}: {message}")
+            }
+            Error::TooLarge { what, count } => {
+                write!(f, "{count} {what} are too many for u32 indices")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
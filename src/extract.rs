@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::{ClassId, Cost, EGraph, Error, NodeId};
+
+/// The result of extraction: a choice of one node for each e-class
+///
+/// With the `serde` feature this can be stored as JSON next to the e-graph it was extracted from,
+/// as an object like `{"choices": {"<class id>": "<node id>"}}`.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtractionResult {
+    pub choices: IndexMap<ClassId, NodeId>,
+}
+
+impl ExtractionResult {
+    /// Chooses `node_id` for `class_id`, replacing any previous choice
+    pub fn choose(&mut self, class_id: ClassId, node_id: NodeId) {
+        self.choices.insert(class_id, node_id);
+    }
+
+    /// Reads choices from a JSON file, which may be compressed like in [`EGraph::from_json_file`]
+    #[cfg(feature = "serde")]
+    pub fn from_json_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let result: Self = serde_json::from_reader(crate::compression::open_json_file(path)?)?;
+        Ok(result)
+    }
+
+    /// Writes choices to a JSON file, compressed like in [`EGraph::to_json_file`]
+    #[cfg(feature = "serde")]
+    pub fn to_json_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        crate::compression::write_json_file(path, self)
+    }
+
+    /// Returns the node chosen for `class_id`
+    ///
+    /// Panics if no node was chosen for it
+    fn chosen(&self, class_id: &ClassId) -> &NodeId {
+        self.choices
+            .get(class_id)
+            .unwrap_or_else(|| panic!("No node chosen for class {class_id:?}"))
+    }
+
+    /// Checks that the choices are well-formed for `egraph`.
+    ///
+    /// Every chosen node must exist and belong to the e-class it was chosen for, every e-class reachable from
+    /// the e-graph's `root_eclasses` must have a chosen node, and the chosen nodes must not form a cycle.
+    pub fn check(&self, egraph: &EGraph) -> Result<(), Error> {
+        for (class_id, node_id) in &self.choices {
+            if egraph.get_node(node_id)?.eclass != *class_id {
+                return Err(Error::WrongClass {
+                    class: class_id.clone(),
+                    node: node_id.clone(),
+                });
+            }
+        }
+        self.topological_order(egraph, &egraph.root_eclasses)?;
+        Ok(())
+    }
+
+    /// The cost of the terms rooted at `roots`, counting every use of a shared sub-term separately.
+    ///
+    /// Returns infinity if the chosen nodes reachable from `roots` form a cycle.
+    /// Panics if a reachable e-class has no chosen node.
+    pub fn tree_cost(&self, egraph: &EGraph, roots: &[ClassId]) -> Cost {
+        let order = match self.topological_order(egraph, roots) {
+            Ok(order) => order,
+            Err(Error::Cycle(_)) => return Cost::new(f64::INFINITY).unwrap(),
+            Err(e) => panic!("{e}"),
+        };
+        let mut costs: HashMap<&ClassId, Cost> = HashMap::new();
+        for class_id in &order {
+            let node = &egraph[self.chosen(class_id)];
+            let cost = node.cost
+                + node
+                    .children
+                    .iter()
+                    .map(|child| costs[egraph.nid_to_cid(child)])
+                    .sum::<Cost>();
+            costs.insert(class_id, cost);
+        }
+        roots.iter().map(|root| costs[root]).sum()
+    }
+
+    /// The cost of the terms rooted at `roots`, counting each chosen node only once.
+    ///
+    /// Panics if a reachable e-class has no chosen node.
+    pub fn dag_cost(&self, egraph: &EGraph, roots: &[ClassId]) -> Cost {
+        let mut seen: HashSet<&ClassId> = HashSet::new();
+        let mut todo: Vec<&ClassId> = roots.iter().collect();
+        let mut cost = Cost::default();
+        while let Some(class_id) = todo.pop() {
+            if !seen.insert(class_id) {
+                continue;
+            }
+            let node = &egraph[self.chosen(class_id)];
+            cost += node.cost;
+            todo.extend(node.children.iter().map(|child| egraph.nid_to_cid(child)));
+        }
+        cost
+    }
+
+    /// Orders the e-classes reachable from `roots` through the chosen nodes so that children come before
+    /// their parents.
+    ///
+    /// Fails if a reachable e-class has no choice, a chosen node or its child is missing, or the chosen nodes
+    /// form a cycle.
+    pub(crate) fn topological_order<'a>(
+        &'a self,
+        egraph: &'a EGraph,
+        roots: &'a [ClassId],
+    ) -> Result<Vec<&'a ClassId>, Error> {
+        enum State {
+            Visiting,
+            Done,
+        }
+        let mut states: HashMap<&ClassId, State> = HashMap::new();
+        let mut order = vec![];
+        // Each entry is a class and whether its children have already been pushed
+        let mut stack: Vec<(&ClassId, bool)> =
+            roots.iter().rev().map(|root| (root, false)).collect();
+        while let Some((class_id, expanded)) = stack.pop() {
+            if expanded {
+                states.insert(class_id, State::Done);
+                order.push(class_id);
+                continue;
+            }
+            if states.contains_key(class_id) {
+                continue;
+            }
+            states.insert(class_id, State::Visiting);
+            stack.push((class_id, true));
+            let node_id = self
+                .choices
+                .get(class_id)
+                .ok_or_else(|| Error::MissingChoice(class_id.clone()))?;
+            for child in egraph.get_node(node_id)?.children.iter().rev() {
+                let child_class = &egraph.get_node(child)?.eclass;
+                match states.get(child_class) {
+                    Some(State::Done) => {}
+                    Some(State::Visiting) => return Err(Error::Cycle(child_class.clone())),
+                    None => stack.push((child_class, false)),
+                }
+            }
+        }
+        Ok(order)
+    }
+}
+
+impl EGraph {
+    /// Extracts the cheapest term for every e-class, using the tree cost of each node.
+    ///
+    /// Starting from the leaves, this repeatedly picks the node with the lowest cost in each e-class,
+    /// where the cost of a node is its own cost plus the costs of its children's e-classes, until nothing
+    /// changes. Subsumed nodes are never chosen, and e-classes without any finite term are left out of the
+    /// result.
+    ///
+    /// Costs should be non-negative; a cycle of negative costs would never reach a fixpoint.
+    pub fn extract_bottom_up(&self) -> ExtractionResult {
+        self.bottom_up(false)
+    }
+
+    /// Like [`EGraph::extract_bottom_up`], but subsumed nodes may be chosen when `include_subsumed` is set
+    pub(crate) fn bottom_up(&self, include_subsumed: bool) -> ExtractionResult {
+        let mut costs: IndexMap<&ClassId, Cost> = IndexMap::new();
+        let mut result = ExtractionResult::default();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (node_id, node) in &self.nodes {
+                if node.subsumed && !include_subsumed {
+                    continue;
+                }
+                let Some(children_cost) = node
+                    .children
+                    .iter()
+                    .map(|child| costs.get(self.nid_to_cid(child)).copied())
+                    .sum::<Option<Cost>>()
+                else {
+                    continue;
+                };
+                let cost = node.cost + children_cost;
+                if costs.get(&node.eclass).is_none_or(|&old| cost < old) {
+                    costs.insert(&node.eclass, cost);
+                    result.choose(node.eclass.clone(), node_id.clone());
+                    changed = true;
+                }
+            }
+        }
+        result
+    }
+}
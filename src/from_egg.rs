@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use egg::{Analysis, EClass, Id, Language};
+
+use crate::{ClassData, ClassId, Cost, EGraph, Node, NodeId};
+
+/// A function computing some data for an egg e-class
+type ClassHook<'a, L, D, T> = Box<dyn Fn(&EClass<L, D>) -> T + 'a>;
+
+/// Converts an [`egg::EGraph`] into a serialized [`EGraph`], with hooks to customize each part
+///
+/// The node ids are `<class id>.<index in class>`, and children point at the first node of their e-class.
+/// The egg e-graph should be rebuilt before converting it.
+///
+/// ```ignore
+/// let serialized = EggConverter::new(&egraph)
+///     .with_cost(|node| Cost::new(node.len() as f64 + 1.0).unwrap())
+///     .with_roots([root])
+///     .convert();
+/// ```
+pub struct EggConverter<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a egg::EGraph<L, N>,
+    op: Box<dyn Fn(&L) -> String + 'a>,
+    cost: Box<dyn Fn(&L) -> Cost + 'a>,
+    class_type: ClassHook<'a, L, N::Data, Option<String>>,
+    class_extra: ClassHook<'a, L, N::Data, HashMap<String, String>>,
+    roots: Vec<Id>,
+}
+
+impl<'a, L: Language + Display, N: Analysis<L>> EggConverter<'a, L, N> {
+    /// A converter that names nodes with their `Display` impl, gives every node a cost of 1, adds no class
+    /// data and no roots
+    pub fn new(egraph: &'a egg::EGraph<L, N>) -> Self {
+        Self::new_with_op(egraph, |node| node.to_string())
+    }
+}
+
+impl<'a, L: Language, N: Analysis<L>> EggConverter<'a, L, N> {
+    /// Like [`EggConverter::new`], but names nodes with `op`, for languages that don't implement `Display`
+    pub fn new_with_op(egraph: &'a egg::EGraph<L, N>, op: impl Fn(&L) -> String + 'a) -> Self {
+        Self {
+            egraph,
+            op: Box::new(op),
+            cost: Box::new(|_| Cost::new(1.0).unwrap()),
+            class_type: Box::new(|_| None),
+            class_extra: Box::new(|_| HashMap::new()),
+            roots: vec![],
+        }
+    }
+
+    pub fn with_op(mut self, op: impl Fn(&L) -> String + 'a) -> Self {
+        self.op = Box::new(op);
+        self
+    }
+
+    pub fn with_cost(mut self, cost: impl Fn(&L) -> Cost + 'a) -> Self {
+        self.cost = Box::new(cost);
+        self
+    }
+
+    /// Sets the [`ClassData::typ`] of each e-class
+    pub fn with_class_type(
+        mut self,
+        class_type: impl Fn(&EClass<L, N::Data>) -> Option<String> + 'a,
+    ) -> Self {
+        self.class_type = Box::new(class_type);
+        self
+    }
+
+    /// Sets the [`ClassData::extra`] of each e-class
+    pub fn with_class_extra(
+        mut self,
+        class_extra: impl Fn(&EClass<L, N::Data>) -> HashMap<String, String> + 'a,
+    ) -> Self {
+        self.class_extra = Box::new(class_extra);
+        self
+    }
+
+    /// Adds e-classes to `root_eclasses`
+    pub fn with_roots(mut self, roots: impl IntoIterator<Item = Id>) -> Self {
+        self.roots.extend(roots);
+        self
+    }
+
+    pub fn convert(self) -> EGraph {
+        let egraph = self.egraph;
+        let mut out = EGraph::default();
+        for class in egraph.classes() {
+            let class_id = ClassId::from(class.id.to_string());
+            for (i, node) in class.nodes.iter().enumerate() {
+                out.add_node(
+                    format!("{}.{i}", class.id),
+                    Node {
+                        op: (self.op)(node),
+                        children: node
+                            .children()
+                            .iter()
+                            .map(|&child| NodeId::from(format!("{}.0", egraph.find(child))))
+                            .collect(),
+                        eclass: class_id.clone(),
+                        cost: (self.cost)(node),
+                        subsumed: false,
+                    },
+                )
+            }
+            let typ = (self.class_type)(class);
+            let extra = (self.class_extra)(class);
+            if typ.is_some() || !extra.is_empty() {
+                out.class_data.insert(class_id, ClassData { typ, extra });
+            }
+        }
+        out.root_eclasses = self
+            .roots
+            .iter()
+            .map(|&root| ClassId::from(egraph.find(root).to_string()))
+            .collect();
+        out
+    }
+}
+
+impl<L: Language + Display, N: Analysis<L>> From<&egg::EGraph<L, N>> for EGraph {
+    fn from(egraph: &egg::EGraph<L, N>) -> Self {
+        EggConverter::new(egraph).convert()
+    }
+}
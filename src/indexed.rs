@@ -0,0 +1,210 @@
+use indexmap::{IndexMap, IndexSet};
+
+use crate::{ClassData, ClassId, Cost, EGraph, Error, Node, NodeId};
+
+/// A frozen view of an [`EGraph`] where nodes and e-classes are numbered with dense `u32` indices
+///
+/// Node indices follow the order of [`EGraph::nodes`] and class indices the order of [`EGraph::classes`].
+/// Children, the nodes of each e-class and the parents of each e-class are stored in flat arrays, so
+/// algorithms can walk the e-graph without hashing ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEGraph {
+    node_ids: IndexSet<NodeId>,
+    class_ids: IndexSet<ClassId>,
+    ops: Vec<String>,
+    costs: Vec<Cost>,
+    subsumed: Vec<bool>,
+    node_classes: Vec<u32>,
+    // Compressed sparse rows: the entries for row `i` are at `offsets[i]..offsets[i + 1]`
+    children_offsets: Vec<u32>,
+    children: Vec<u32>,
+    class_nodes_offsets: Vec<u32>,
+    class_nodes: Vec<u32>,
+    parents_offsets: Vec<u32>,
+    parents: Vec<u32>,
+    roots: Vec<u32>,
+    class_data: IndexMap<ClassId, ClassData>,
+}
+
+impl IndexedEGraph {
+    /// Builds the indexed view, failing if a child or root e-class is missing, or if there are too many nodes or
+    /// children to number with `u32`
+    pub fn new(egraph: &EGraph) -> Result<Self, Error> {
+        let class_ids: IndexSet<ClassId> = egraph.classes().keys().cloned().collect();
+        let node_ids: IndexSet<NodeId> = egraph.nodes.keys().cloned().collect();
+        // There are never more e-classes than nodes, so this bounds every node and class index
+        to_index(node_ids.len(), "nodes")?;
+        let class_index = |class_id: &ClassId| class_ids.get_index_of(class_id).unwrap() as u32;
+
+        let mut children_offsets = vec![0];
+        let mut children = vec![];
+        for node in egraph.nodes.values() {
+            for child in &node.children {
+                let child = node_ids
+                    .get_index_of(child)
+                    .ok_or_else(|| Error::MissingNode(child.clone()))?;
+                children.push(child as u32);
+            }
+            // Parents are at most one per child, so this bounds the parent offsets too
+            children_offsets.push(to_index(children.len(), "children")?);
+        }
+
+        let mut class_nodes_offsets = vec![0];
+        let mut class_nodes = vec![];
+        for class in egraph.classes().values() {
+            class_nodes.extend(
+                class
+                    .nodes
+                    .iter()
+                    .map(|node_id| node_ids.get_index_of(node_id).unwrap() as u32),
+            );
+            class_nodes_offsets.push(class_nodes.len() as u32);
+        }
+
+        let node_classes: Vec<u32> = egraph
+            .nodes
+            .values()
+            .map(|node| class_index(&node.eclass))
+            .collect();
+
+        let mut class_parents: Vec<Vec<u32>> = vec![vec![]; class_ids.len()];
+        for (parent, window) in children_offsets.windows(2).enumerate() {
+            for &child in &children[window[0] as usize..window[1] as usize] {
+                let parents = &mut class_parents[node_classes[child as usize] as usize];
+                if parents.last() != Some(&(parent as u32)) {
+                    parents.push(parent as u32);
+                }
+            }
+        }
+        let mut parents_offsets = vec![0];
+        let mut parents = vec![];
+        for class_parents in class_parents {
+            parents.extend(class_parents);
+            parents_offsets.push(parents.len() as u32);
+        }
+
+        let roots = egraph
+            .root_eclasses
+            .iter()
+            .map(|root| {
+                class_ids
+                    .get_index_of(root)
+                    .map(|i| i as u32)
+                    .ok_or_else(|| Error::MissingClass(root.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            ops: egraph.nodes.values().map(|node| node.op.clone()).collect(),
+            costs: egraph.nodes.values().map(|node| node.cost).collect(),
+            subsumed: egraph.nodes.values().map(|node| node.subsumed).collect(),
+            node_ids,
+            class_ids,
+            node_classes,
+            children_offsets,
+            children,
+            class_nodes_offsets,
+            class_nodes,
+            parents_offsets,
+            parents,
+            roots,
+            class_data: egraph.class_data.clone(),
+        })
+    }
+
+    /// Converts the view back into an [`EGraph`]
+    pub fn to_egraph(&self) -> EGraph {
+        let mut egraph = EGraph::default();
+        for (i, node_id) in self.node_ids.iter().enumerate() {
+            let node = Node {
+                op: self.ops[i].clone(),
+                children: self
+                    .children(i as u32)
+                    .iter()
+                    .map(|&child| self.node_id(child).clone())
+                    .collect(),
+                eclass: self.class_id(self.node_classes[i]).clone(),
+                cost: self.costs[i],
+                subsumed: self.subsumed[i],
+            };
+            egraph.add_node(node_id.clone(), node);
+        }
+        egraph.root_eclasses = self
+            .roots
+            .iter()
+            .map(|&root| self.class_id(root).clone())
+            .collect();
+        egraph.class_data = self.class_data.clone();
+        egraph
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.node_ids.len()
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.class_ids.len()
+    }
+
+    pub fn node_id(&self, node: u32) -> &NodeId {
+        &self.node_ids[node as usize]
+    }
+
+    pub fn class_id(&self, class: u32) -> &ClassId {
+        &self.class_ids[class as usize]
+    }
+
+    pub fn node_index(&self, node_id: &NodeId) -> Option<u32> {
+        self.node_ids.get_index_of(node_id).map(|i| i as u32)
+    }
+
+    pub fn class_index(&self, class_id: &ClassId) -> Option<u32> {
+        self.class_ids.get_index_of(class_id).map(|i| i as u32)
+    }
+
+    pub fn op(&self, node: u32) -> &str {
+        &self.ops[node as usize]
+    }
+
+    pub fn cost(&self, node: u32) -> Cost {
+        self.costs[node as usize]
+    }
+
+    pub fn is_subsumed(&self, node: u32) -> bool {
+        self.subsumed[node as usize]
+    }
+
+    /// The e-class of a node
+    pub fn node_class(&self, node: u32) -> u32 {
+        self.node_classes[node as usize]
+    }
+
+    /// The child nodes of a node, in order
+    pub fn children(&self, node: u32) -> &[u32] {
+        row(&self.children_offsets, &self.children, node)
+    }
+
+    /// The nodes in an e-class
+    pub fn class_nodes(&self, class: u32) -> &[u32] {
+        row(&self.class_nodes_offsets, &self.class_nodes, class)
+    }
+
+    /// The nodes with a child in an e-class, each listed once
+    pub fn parents(&self, class: u32) -> &[u32] {
+        row(&self.parents_offsets, &self.parents, class)
+    }
+
+    /// The root e-classes
+    pub fn roots(&self) -> &[u32] {
+        &self.roots
+    }
+}
+
+fn row<'a>(offsets: &[u32], values: &'a [u32], i: u32) -> &'a [u32] {
+    &values[offsets[i as usize] as usize..offsets[i as usize + 1] as usize]
+}
+
+/// Converts a count or index to `u32`, failing if there are more `what` than fit
+fn to_index(n: usize, what: &'static str) -> Result<u32, Error> {
+    u32::try_from(n).map_err(|_| Error::TooLarge { what, count: n })
+}
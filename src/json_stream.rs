@@ -0,0 +1,156 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use indexmap::IndexMap;
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+
+use crate::version::FormatVersion;
+use crate::{ClassData, ClassId, EGraph, Node, NodeId, FORMAT_VERSION};
+
+impl EGraph {
+    /// Reads an e-graph in the JSON format one node at a time, calling `on_node` with each node as soon as it
+    /// is parsed instead of keeping it.
+    ///
+    /// Returns everything else in the document, the root e-classes and class data, in an e-graph without
+    /// any nodes. Like [`EGraph::from_json_file`], older format versions are upgraded.
+    ///
+    /// A `version` newer than [`FORMAT_VERSION`] is rejected as soon as it is read, so if it comes before
+    /// `nodes`, as in everything written by this crate, `on_node` is never called. If it comes after `nodes`,
+    /// or is missing, every node has already been passed to `on_node` by the time the version is known.
+    pub fn visit_json_nodes(
+        reader: impl Read,
+        mut on_node: impl FnMut(NodeId, Node),
+    ) -> io::Result<EGraph> {
+        let mut deserializer = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+        let mut egraph = Document {
+            on_node: &mut on_node,
+        }
+        .deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        egraph.migrate();
+        Ok(egraph)
+    }
+}
+
+/// Writes an e-graph in the JSON format one node at a time, so the whole e-graph never has to be in memory
+///
+/// Nodes are written in the order they are given, and it is up to the caller to not repeat a node id.
+/// Writes are small, so `W` should usually be buffered.
+pub struct JsonWriter<W: Write> {
+    writer: W,
+    empty: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        write!(writer, r#"{{"version":{FORMAT_VERSION},"nodes":{{"#)?;
+        Ok(Self {
+            writer,
+            empty: true,
+        })
+    }
+
+    pub fn write_node(&mut self, node_id: &NodeId, node: &Node) -> io::Result<()> {
+        if !self.empty {
+            self.writer.write_all(b",")?;
+        }
+        self.empty = false;
+        serde_json::to_writer(&mut self.writer, node_id)?;
+        self.writer.write_all(b":")?;
+        serde_json::to_writer(&mut self.writer, node)?;
+        Ok(())
+    }
+
+    /// Writes the rest of the e-graph after the nodes and returns the underlying writer
+    pub fn finish(
+        mut self,
+        root_eclasses: &[ClassId],
+        class_data: &IndexMap<ClassId, ClassData>,
+    ) -> io::Result<W> {
+        self.writer.write_all(br#"},"root_eclasses":"#)?;
+        serde_json::to_writer(&mut self.writer, root_eclasses)?;
+        self.writer.write_all(br#","class_data":"#)?;
+        serde_json::to_writer(&mut self.writer, class_data)?;
+        self.writer.write_all(b"}")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// The top level JSON object, passing each node to `on_node` and collecting the rest
+struct Document<'a, F> {
+    on_node: &'a mut F,
+}
+
+impl<'de, F: FnMut(NodeId, Node)> DeserializeSeed<'de> for Document<'_, F> {
+    type Value = EGraph;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<EGraph, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(NodeId, Node)> Visitor<'de> for Document<'_, F> {
+    type Value = EGraph;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an e-graph")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<EGraph, A::Error> {
+        let mut egraph = EGraph {
+            version: FormatVersion::legacy(),
+            ..Default::default()
+        };
+        let mut seen_nodes = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "nodes" => {
+                    map.next_value_seed(Nodes {
+                        on_node: &mut *self.on_node,
+                    })?;
+                    seen_nodes = true;
+                }
+                // Fails right away on a newer version, before any later nodes are read
+                "version" => egraph.version = map.next_value()?,
+                "root_eclasses" => egraph.root_eclasses = map.next_value()?,
+                "class_data" => egraph.class_data = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !seen_nodes {
+            return Err(de::Error::missing_field("nodes"));
+        }
+        Ok(egraph)
+    }
+}
+
+/// The `nodes` object, passing each entry to `on_node`
+struct Nodes<'a, F> {
+    on_node: &'a mut F,
+}
+
+impl<'de, F: FnMut(NodeId, Node)> DeserializeSeed<'de> for Nodes<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(NodeId, Node)> Visitor<'de> for Nodes<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of node ids to nodes")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some((node_id, node)) = map.next_entry::<NodeId, Node>()? {
+            (self.on_node)(node_id, node);
+        }
+        Ok(())
+    }
+}
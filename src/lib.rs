@@ -1,7 +1,35 @@
 #[cfg(feature = "graphviz")]
 mod graphviz;
 
+#[cfg(feature = "binary")]
+mod binary;
+
+#[cfg(feature = "egg")]
+mod from_egg;
+
+#[cfg(feature = "petgraph")]
+mod petgraph;
+
 mod algorithms;
+#[cfg(feature = "serde")]
+mod compression;
+mod cycles;
+mod egglog;
+mod error;
+mod extract;
+mod indexed;
+#[cfg(feature = "serde")]
+mod json_stream;
+mod rebuild;
+#[cfg(feature = "serde")]
+mod schema;
+mod sexp;
+mod stats;
+mod term;
+mod union;
+mod union_find;
+mod validate;
+mod version;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -10,6 +38,26 @@ use indexmap::{map::Entry, IndexMap};
 use once_cell::sync::OnceCell;
 use ordered_float::NotNan;
 
+pub use algorithms::{RetainStats, MISSING_ARG_VALUE};
+pub use cycles::CycleReport;
+pub use egglog::DEFAULT_SORT;
+pub use error::Error;
+pub use extract::ExtractionResult;
+#[cfg(feature = "egg")]
+pub use from_egg::EggConverter;
+pub use indexed::IndexedEGraph;
+#[cfg(feature = "serde")]
+pub use json_stream::JsonWriter;
+#[cfg(feature = "petgraph")]
+pub use petgraph::{PetgraphEdge, PetgraphNode};
+pub use rebuild::RebuildReport;
+pub use sexp::SexpBuilder;
+pub use stats::{Stats, TypeStats};
+pub use term::Term;
+pub use union::{KeepFirst, MergePolicy};
+pub use validate::ValidationError;
+pub use version::FORMAT_VERSION;
+
 pub type Cost = NotNan<f64>;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -60,17 +108,20 @@ mod id_impls {
     }
 }
 
+// Deserializing goes through `RawEGraph`, which upgrades documents written in older format versions
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", serde(from = "version::RawEGraph"))]
+#[derive(Debug, Default, Clone, Eq)]
 pub struct EGraph {
+    version: version::FormatVersion,
     pub nodes: IndexMap<NodeId, Node>,
-    #[cfg_attr(feature = "serde", serde(default))]
     pub root_eclasses: Vec<ClassId>,
     // Optional mapping of e-class ids to some additional data about the e-class
-    #[cfg_attr(feature = "serde", serde(default))]
     pub class_data: IndexMap<ClassId, ClassData>,
     #[cfg_attr(feature = "serde", serde(skip))]
     once_cell_classes: OnceCell<IndexMap<ClassId, Class>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    once_cell_parents: OnceCell<IndexMap<ClassId, Vec<(NodeId, usize)>>>,
 }
 
 impl EGraph {
@@ -89,6 +140,35 @@ impl EGraph {
             }
             Entry::Vacant(e) => e.insert(node),
         };
+        self.invalidate_caches();
+    }
+
+    /// Adds a new node to the egraph
+    ///
+    /// Returns an error instead of panicking if a node with the same id already exists
+    pub fn try_add_node(&mut self, node_id: impl Into<NodeId>, node: Node) -> Result<(), Error> {
+        match self.nodes.entry(node_id.into()) {
+            Entry::Occupied(e) => Err(Error::DuplicateNode(e.key().clone())),
+            Entry::Vacant(e) => {
+                e.insert(node);
+                self.invalidate_caches();
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the node with the given id, or an error if there is none
+    pub fn get_node(&self, node_id: &NodeId) -> Result<&Node, Error> {
+        self.nodes
+            .get(node_id)
+            .ok_or_else(|| Error::MissingNode(node_id.clone()))
+    }
+
+    /// Returns the class with the given id, or an error if there is none
+    pub fn get_class(&self, class_id: &ClassId) -> Result<&Class, Error> {
+        self.classes()
+            .get(class_id)
+            .ok_or_else(|| Error::MissingClass(class_id.clone()))
     }
 
     pub fn nid_to_cid(&self, node_id: &NodeId) -> &ClassId {
@@ -99,10 +179,19 @@ impl EGraph {
         &self[&self[node_id].eclass]
     }
 
+    /// Mutable access to the nodes, which also clears the cached [`EGraph::classes`] and [`EGraph::parents`]
+    ///
+    /// Prefer this over writing to the `nodes` field directly, which the cache cannot see.
+    pub fn nodes_mut(&mut self) -> &mut IndexMap<NodeId, Node> {
+        self.invalidate_caches();
+        &mut self.nodes
+    }
+
     /// Groups the nodes in the e-graph by their e-class
     ///
-    /// This is *only done once* and then the result is cached.
-    /// Modifications to the e-graph will not be reflected
+    /// The result is cached until the e-graph is modified through one of its methods,
+    /// like [`EGraph::add_node`] or [`EGraph::nodes_mut`].
+    /// Changes made by writing to the `nodes` field directly will not be reflected
     /// in later calls to this function.
     pub fn classes(&self) -> &IndexMap<ClassId, Class> {
         self.once_cell_classes.get_or_init(|| {
@@ -121,18 +210,62 @@ impl EGraph {
         })
     }
 
+    /// Maps each e-class to the nodes that have a child in it, along with the position of that child
+    ///
+    /// E-classes without parents are left out, as are children that are not in the e-graph.
+    /// Like [`EGraph::classes`], the result is cached until the e-graph is modified through one of its methods,
+    /// and does not see writes to the `nodes` field. Methods that take `&mut self` rebuild it before using it.
+    pub fn parents(&self) -> &IndexMap<ClassId, Vec<(NodeId, usize)>> {
+        self.once_cell_parents.get_or_init(|| {
+            let mut parents: IndexMap<ClassId, Vec<(NodeId, usize)>> = IndexMap::new();
+            for (node_id, node) in &self.nodes {
+                for (position, child) in node.children.iter().enumerate() {
+                    if let Some(child) = self.nodes.get(child) {
+                        parents
+                            .entry(child.eclass.clone())
+                            .or_default()
+                            .push((node_id.clone(), position));
+                    }
+                }
+            }
+            parents
+        })
+    }
+
+    /// The nodes that have a child in `class_id`, along with the position of that child
+    pub fn class_parents(&self, class_id: &ClassId) -> &[(NodeId, usize)] {
+        self.parents().get(class_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Clears every cached view of the nodes, so that it is recomputed on next use
+    fn invalidate_caches(&mut self) {
+        self.once_cell_classes.take();
+        self.once_cell_parents.take();
+    }
+
+    /// Clears the caches at the start of a method that takes `&mut self` and reads them, since the public
+    /// `nodes` may have been edited directly since they were built
+    fn refresh_caches(&mut self) {
+        self.invalidate_caches();
+    }
+
+    /// Reads an e-graph from a JSON file, upgrading it if it was written in an older format version
+    ///
+    /// Files compressed with gzip or zstd are detected by their first bytes and decompressed when the
+    /// `gzip` or `zstd` feature is enabled.
     #[cfg(feature = "serde")]
     pub fn from_json_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
-        let file = std::fs::File::open(path)?;
-        let egraph: Self = serde_json::from_reader(std::io::BufReader::new(file))?;
+        let egraph: Self = serde_json::from_reader(compression::open_json_file(path)?)?;
         Ok(egraph)
     }
 
+    /// Writes the e-graph to a JSON file
+    ///
+    /// Paths ending in `.gz` or `.zst` are compressed with gzip or zstd, which needs the `gzip` or `zstd`
+    /// feature.
     #[cfg(feature = "serde")]
     pub fn to_json_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
-        let file = std::fs::File::create(path)?;
-        serde_json::to_writer_pretty(std::io::BufWriter::new(file), self)?;
-        Ok(())
+        compression::write_json_file(path, self)
     }
 
     #[cfg(feature = "serde")]
@@ -143,6 +276,15 @@ impl EGraph {
     }
 }
 
+// Caches are derived from the other fields, so they are left out of comparisons
+impl PartialEq for EGraph {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+            && self.root_eclasses == other.root_eclasses
+            && self.class_data == other.class_data
+    }
+}
+
 impl std::ops::Index<&NodeId> for EGraph {
     type Output = Node;
 
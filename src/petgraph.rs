@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use ::petgraph::graph::{DiGraph, NodeIndex};
+use ::petgraph::visit::EdgeRef;
+use ::petgraph::Direction;
+use indexmap::IndexSet;
+
+use crate::{ClassData, ClassId, Cost, EGraph, Error, Node, NodeId};
+
+/// A vertex in the graph made by [`EGraph::to_petgraph`]: an e-class, a node, or a missing child
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PetgraphNode {
+    Class {
+        id: ClassId,
+        data: Option<ClassData>,
+        /// The positions of this e-class in `root_eclasses`, which can list it more than once
+        root_positions: Vec<usize>,
+    },
+    Node {
+        id: NodeId,
+        op: String,
+        cost: Cost,
+        subsumed: bool,
+    },
+    /// A child that some node lists but that is not in the e-graph
+    Missing { id: NodeId },
+}
+
+/// An edge in the graph made by [`EGraph::to_petgraph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PetgraphEdge {
+    /// From an e-class to one of its nodes
+    Member,
+    /// From a node to its child at this position
+    Child(usize),
+}
+
+impl EGraph {
+    /// Converts the e-graph into a bipartite petgraph graph, with a vertex for each e-class and each node.
+    ///
+    /// E-classes point to their nodes with [`PetgraphEdge::Member`] edges and nodes point to their children
+    /// with [`PetgraphEdge::Child`] edges. Root e-classes come first, in order. Children that are not in the
+    /// e-graph get a [`PetgraphNode::Missing`] vertex each, so that [`EGraph::from_petgraph`] gives back the
+    /// same e-graph.
+    pub fn to_petgraph(&self) -> DiGraph<PetgraphNode, PetgraphEdge> {
+        let mut graph = DiGraph::new();
+        let class_ids: IndexSet<&ClassId> = self
+            .root_eclasses
+            .iter()
+            .chain(self.classes().keys())
+            .chain(self.class_data.keys())
+            .collect();
+        let mut class_indices = HashMap::new();
+        for class_id in class_ids {
+            let index = graph.add_node(PetgraphNode::Class {
+                id: class_id.clone(),
+                data: self.class_data.get(class_id).cloned(),
+                root_positions: self
+                    .root_eclasses
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, root)| *root == class_id)
+                    .map(|(position, _)| position)
+                    .collect(),
+            });
+            class_indices.insert(class_id, index);
+        }
+        let mut node_indices = HashMap::new();
+        for (node_id, node) in &self.nodes {
+            let index = graph.add_node(PetgraphNode::Node {
+                id: node_id.clone(),
+                op: node.op.clone(),
+                cost: node.cost,
+                subsumed: node.subsumed,
+            });
+            graph.add_edge(class_indices[&node.eclass], index, PetgraphEdge::Member);
+            node_indices.insert(node_id, index);
+        }
+        for (node_id, node) in &self.nodes {
+            for (position, child) in node.children.iter().enumerate() {
+                let child_index = *node_indices
+                    .entry(child)
+                    .or_insert_with(|| graph.add_node(PetgraphNode::Missing { id: child.clone() }));
+                graph.add_edge(
+                    node_indices[node_id],
+                    child_index,
+                    PetgraphEdge::Child(position),
+                );
+            }
+        }
+        graph
+    }
+
+    /// Converts a graph in the shape made by [`EGraph::to_petgraph`] back into an e-graph.
+    ///
+    /// Every node must be the target of exactly one [`PetgraphEdge::Member`] edge from an e-class, and the
+    /// children of a node are ordered by their [`PetgraphEdge::Child`] positions. Root e-classes are ordered by
+    /// their `root_positions`.
+    pub fn from_petgraph(graph: &DiGraph<PetgraphNode, PetgraphEdge>) -> Result<EGraph, Error> {
+        let mut egraph = EGraph::default();
+        let id_of = |index: NodeIndex| match &graph[index] {
+            PetgraphNode::Class { id, .. } => id.to_string(),
+            PetgraphNode::Node { id, .. } | PetgraphNode::Missing { id } => id.to_string(),
+        };
+        for edge in graph.edge_references() {
+            let valid = match edge.weight() {
+                PetgraphEdge::Member => {
+                    matches!(graph[edge.source()], PetgraphNode::Class { .. })
+                        && matches!(graph[edge.target()], PetgraphNode::Node { .. })
+                }
+                PetgraphEdge::Child(_) => {
+                    matches!(graph[edge.source()], PetgraphNode::Node { .. })
+                        && matches!(
+                            graph[edge.target()],
+                            PetgraphNode::Node { .. } | PetgraphNode::Missing { .. }
+                        )
+                }
+            };
+            if !valid {
+                return Err(Error::InvalidEdge {
+                    source: id_of(edge.source()),
+                    target: id_of(edge.target()),
+                });
+            }
+        }
+
+        let mut roots: Vec<(usize, ClassId)> = vec![];
+        for index in graph.node_indices() {
+            match &graph[index] {
+                PetgraphNode::Class {
+                    id,
+                    data,
+                    root_positions,
+                } => {
+                    if let Some(data) = data {
+                        egraph.class_data.insert(id.clone(), data.clone());
+                    }
+                    roots.extend(
+                        root_positions
+                            .iter()
+                            .map(|&position| (position, id.clone())),
+                    );
+                }
+                PetgraphNode::Missing { .. } => {}
+                PetgraphNode::Node {
+                    id,
+                    op,
+                    cost,
+                    subsumed,
+                } => {
+                    let mut classes = graph
+                        .edges_directed(index, Direction::Incoming)
+                        .filter(|edge| *edge.weight() == PetgraphEdge::Member)
+                        .map(|edge| edge.source());
+                    let (Some(class), None) = (classes.next(), classes.next()) else {
+                        return Err(Error::NodeClass(id.clone()));
+                    };
+                    let PetgraphNode::Class { id: eclass, .. } = &graph[class] else {
+                        unreachable!("member edges start at classes");
+                    };
+                    let mut children: Vec<(usize, NodeId)> = graph
+                        .edges_directed(index, Direction::Outgoing)
+                        .filter_map(|edge| match (edge.weight(), &graph[edge.target()]) {
+                            (
+                                PetgraphEdge::Child(position),
+                                PetgraphNode::Node { id, .. } | PetgraphNode::Missing { id },
+                            ) => Some((*position, id.clone())),
+                            _ => None,
+                        })
+                        .collect();
+                    children.sort_by_key(|(position, _)| *position);
+                    egraph.try_add_node(
+                        id.clone(),
+                        Node {
+                            op: op.clone(),
+                            children: children.into_iter().map(|(_, child)| child).collect(),
+                            eclass: eclass.clone(),
+                            cost: *cost,
+                            subsumed: *subsumed,
+                        },
+                    )?;
+                }
+            }
+        }
+        roots.sort_by_key(|(position, _)| *position);
+        egraph.root_eclasses = roots.into_iter().map(|(_, root)| root).collect();
+        Ok(egraph)
+    }
+}
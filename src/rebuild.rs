@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::union_find::UnionFind;
+use crate::{ClassId, EGraph, Error, KeepFirst, MergePolicy, NodeId};
+
+/// What was merged by [`EGraph::rebuild`]
+///
+/// If no e-classes were merged, the e-graph was already congruence-closed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RebuildReport {
+    /// Each e-class that was merged away, mapped to the e-class it was merged into
+    pub merged_classes: IndexMap<ClassId, ClassId>,
+    /// Each node that was removed as a duplicate, mapped to the node that replaced it
+    pub merged_nodes: IndexMap<NodeId, NodeId>,
+}
+
+impl RebuildReport {
+    /// Whether the rebuild left the e-graph unchanged
+    pub fn is_empty(&self) -> bool {
+        self.merged_classes.is_empty() && self.merged_nodes.is_empty()
+    }
+}
+
+impl EGraph {
+    /// Restores congruence: whenever two nodes have the same op and children in the same e-classes, their
+    /// e-classes are merged, until no more merges are possible.
+    ///
+    /// Each merged e-class keeps the id that comes first in [`EGraph::classes`], and takes over the class data
+    /// of the others, keeping its own values where both set a field, like in [`EGraph::union`].
+    /// Afterwards, nodes with the same op and children e-classes are duplicates, so only the first is kept,
+    /// with the lowest cost among them, and subsumed only if all of them were. Children that pointed to a
+    /// removed node point to the kept node instead, and `root_eclasses` is remapped and deduplicated.
+    ///
+    /// Fails without changing anything if a node has a child that is not in the e-graph.
+    pub fn rebuild(&mut self) -> Result<RebuildReport, Error> {
+        self.rebuild_with(&mut KeepFirst)
+    }
+
+    /// Like [`EGraph::rebuild`], but combines the class data of merged e-classes with `policy`
+    pub fn rebuild_with(&mut self, policy: &mut impl MergePolicy) -> Result<RebuildReport, Error> {
+        for child in self.nodes.values().flat_map(|node| &node.children) {
+            self.get_node(child)?;
+        }
+
+        // Find the e-class of every node by index, and merge e-classes until congruence holds
+        let class_ids: IndexSet<ClassId> = self
+            .nodes
+            .values()
+            .map(|node| node.eclass.clone())
+            .collect();
+        let mut union_find = UnionFind::default();
+        for _ in &class_ids {
+            union_find.make_set();
+        }
+        let node_classes: Vec<usize> = self
+            .nodes
+            .values()
+            .map(|node| class_ids.get_index_of(&node.eclass).unwrap())
+            .collect();
+        let child_classes: Vec<Vec<usize>> = self
+            .nodes
+            .values()
+            .map(|node| {
+                node.children
+                    .iter()
+                    .map(|child| node_classes[self.nodes.get_index_of(child).unwrap()])
+                    .collect()
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut signatures: HashMap<(&str, Vec<usize>), usize> = HashMap::new();
+            for (index, node) in self.nodes.values().enumerate() {
+                let children = child_classes[index]
+                    .iter()
+                    .map(|&class| union_find.find(class))
+                    .collect();
+                let class = union_find.find(node_classes[index]);
+                let first = *signatures.entry((&node.op, children)).or_insert(class);
+                if union_find.find(first) != class {
+                    union_find.union(first, class);
+                    changed = true;
+                }
+            }
+        }
+
+        // Congruent nodes are now in the same e-class, so the first one with each signature is kept
+        let mut report = RebuildReport::default();
+        let mut kept: HashMap<(&str, Vec<usize>), &NodeId> = HashMap::new();
+        for (index, (node_id, node)) in self.nodes.iter().enumerate() {
+            let children = child_classes[index]
+                .iter()
+                .map(|&class| union_find.find(class))
+                .collect();
+            let kept_id = *kept.entry((&node.op, children)).or_insert(node_id);
+            if kept_id != node_id {
+                report.merged_nodes.insert(node_id.clone(), kept_id.clone());
+            }
+        }
+        for (index, class_id) in class_ids.iter().enumerate() {
+            let root = union_find.find(index);
+            if root != index {
+                report
+                    .merged_classes
+                    .insert(class_id.clone(), class_ids[root].clone());
+            }
+        }
+        if report.is_empty() {
+            return Ok(report);
+        }
+
+        self.replace_nodes(&report.merged_nodes);
+        for node in self.nodes.values_mut() {
+            if let Some(class_id) = report.merged_classes.get(&node.eclass) {
+                node.eclass = class_id.clone();
+            }
+        }
+
+        for (merged, kept) in &report.merged_classes {
+            self.merge_class_data(kept, merged, policy);
+        }
+
+        let mut roots = IndexSet::new();
+        for root in &self.root_eclasses {
+            roots.insert(report.merged_classes.get(root).unwrap_or(root).clone());
+        }
+        self.root_eclasses = roots.into_iter().collect();
+
+        self.invalidate_caches();
+        Ok(report)
+    }
+
+    /// Removes nodes that have the same op, children and e-class as an earlier node, returning each removed
+    /// node mapped to the node that replaced it.
+    ///
+    /// The kept node gets the lowest cost among its duplicates, and is subsumed only if all of them were.
+    /// Children that pointed to a removed node point to the kept node instead, which can make their parents
+    /// duplicates in turn, so this repeats until there are no duplicates left.
+    pub fn dedup_nodes(&mut self) -> IndexMap<NodeId, NodeId> {
+        let mut merged: IndexMap<NodeId, NodeId> = IndexMap::new();
+        loop {
+            let mut kept = HashMap::new();
+            let mut duplicates = IndexMap::new();
+            for (node_id, node) in &self.nodes {
+                let key = (&node.op, &node.children, &node.eclass);
+                let kept_id: &NodeId = kept.entry(key).or_insert(node_id);
+                if kept_id != node_id {
+                    duplicates.insert(node_id.clone(), kept_id.clone());
+                }
+            }
+            if duplicates.is_empty() {
+                break;
+            }
+            self.replace_nodes(&duplicates);
+            // A node kept in an earlier pass may have been removed in this one
+            for kept_id in merged.values_mut() {
+                if let Some(new_id) = duplicates.get(kept_id) {
+                    *kept_id = new_id.clone();
+                }
+            }
+            merged.extend(duplicates);
+        }
+        if !merged.is_empty() {
+            self.invalidate_caches();
+        }
+        merged
+    }
+
+    /// Removes each node in `replacements` in favor of the node it maps to, which takes the lowest cost of the
+    /// two and stays subsumed only if both were, and points children at the kept nodes
+    fn replace_nodes(&mut self, replacements: &IndexMap<NodeId, NodeId>) {
+        for (removed, kept) in replacements {
+            let (cost, subsumed) = (self.nodes[removed].cost, self.nodes[removed].subsumed);
+            let kept = &mut self.nodes[kept];
+            kept.cost = kept.cost.min(cost);
+            kept.subsumed &= subsumed;
+        }
+        self.nodes
+            .retain(|node_id, _| !replacements.contains_key(node_id));
+        for node in self.nodes.values_mut() {
+            for child in &mut node.children {
+                if let Some(kept) = replacements.get(child) {
+                    *child = kept.clone();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use serde_json::{json, Value};
+
+use crate::{EGraph, FORMAT_VERSION};
+
+impl EGraph {
+    /// A [JSON Schema](https://json-schema.org/) (draft 2020-12) describing the JSON format read by
+    /// [`EGraph::from_json_file`], including the defaults used for missing fields.
+    pub fn json_schema() -> Value {
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "EGraph",
+            "description": "A serialized e-graph",
+            "type": "object",
+            "required": ["nodes"],
+            "properties": {
+                "version": {
+                    "description": "The format version, where a missing version means 0",
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": FORMAT_VERSION,
+                    "default": 0
+                },
+                "nodes": {
+                    "description": "All nodes, keyed by their id",
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/$defs/Node" }
+                },
+                "root_eclasses": {
+                    "description": "The e-classes to extract terms from",
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/ClassId" },
+                    "default": []
+                },
+                "class_data": {
+                    "description": "Additional data about e-classes, keyed by their id",
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/$defs/ClassData" },
+                    "default": {}
+                }
+            },
+            "$defs": {
+                "NodeId": { "type": "string" },
+                "ClassId": { "type": "string" },
+                "Node": {
+                    "type": "object",
+                    "required": ["op", "eclass"],
+                    "properties": {
+                        "op": { "type": "string" },
+                        "children": {
+                            "description": "The ids of the child nodes, in order",
+                            "type": "array",
+                            "items": { "$ref": "#/$defs/NodeId" },
+                            "default": []
+                        },
+                        "eclass": { "$ref": "#/$defs/ClassId" },
+                        "cost": { "type": "number", "default": 1.0 },
+                        "subsumed": { "type": "boolean", "default": false }
+                    }
+                },
+                "ClassData": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "type": ["string", "null"] }
+                    },
+                    "additionalProperties": { "type": "string" }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use indexmap::IndexSet;
+
+use crate::union_find::UnionFind;
+use crate::{ClassId, EGraph, Error, Node, NodeId};
+
+/// A parsed s-expression, with the byte offset where each list starts
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>, usize),
+}
+
+fn parse_error(position: usize, message: &str) -> Error {
+    Error::Parse {
+        position,
+        message: message.to_string(),
+    }
+}
+
+/// Parses every s-expression in `input`.
+///
+/// Atoms are separated by whitespace and parentheses, and `;` starts a comment that runs to the end of the line.
+/// Strings in double quotes are single atoms, quotes included, so `"a b"` is kept as the op `"a b"`.
+fn parse(input: &str) -> Result<Vec<Sexp>, Error> {
+    let mut top = vec![];
+    // The start and items of each list that is still open
+    let mut stack: Vec<(usize, Vec<Sexp>)> = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let sexp = match c {
+            c if c.is_whitespace() => continue,
+            ';' => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                continue;
+            }
+            '(' => {
+                stack.push((start, vec![]));
+                continue;
+            }
+            ')' => {
+                let (start, items) = stack
+                    .pop()
+                    .ok_or_else(|| parse_error(start, "unexpected `)`"))?;
+                Sexp::List(items, start)
+            }
+            '"' => {
+                let mut end = None;
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = Some(i + 1);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                let end = end.ok_or_else(|| parse_error(start, "unterminated string"))?;
+                Sexp::Atom(input[start..end].to_string())
+            }
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars
+                    .next_if(|&(_, c)| !c.is_whitespace() && !matches!(c, '(' | ')' | ';' | '"'))
+                {
+                    end = i + c.len_utf8();
+                }
+                Sexp::Atom(input[start..end].to_string())
+            }
+        };
+        match stack.last_mut() {
+            Some((_, items)) => items.push(sexp),
+            None => top.push(sexp),
+        }
+    }
+    if let Some((start, _)) = stack.pop() {
+        return Err(parse_error(start, "unclosed `(`"));
+    }
+    Ok(top)
+}
+
+fn is_equality(items: &[Sexp]) -> bool {
+    matches!(items.first(), Some(Sexp::Atom(op)) if op == "=")
+}
+
+/// Builds an [`EGraph`] out of terms written as s-expressions.
+///
+/// A term is either an atom, which becomes a leaf node with the atom as its op, or a list like `(f a b)`.
+/// Identical sub-terms are shared, so each distinct term becomes exactly one node, with a cost of one.
+/// Every node starts out in its own e-class until it is merged with [`SexpBuilder::union`].
+///
+/// Node ids are the order in which terms were first added, and each e-class is named after its first node.
+#[derive(Debug, Default, Clone)]
+pub struct SexpBuilder {
+    nodes: IndexSet<(String, Vec<usize>)>,
+    union_find: UnionFind,
+    roots: Vec<usize>,
+}
+
+impl SexpBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a single term and adds it, returning the id of its top node
+    pub fn add(&mut self, term: &str) -> Result<NodeId, Error> {
+        let mut sexps = parse(term)?;
+        if sexps.len() != 1 {
+            return Err(parse_error(0, "expected exactly one term"));
+        }
+        let index = self.add_sexp(&sexps.pop().unwrap())?;
+        Ok(index.to_string().into())
+    }
+
+    /// Puts the nodes `a` and `b` in the same e-class
+    pub fn union(&mut self, a: &NodeId, b: &NodeId) -> Result<(), Error> {
+        let (a, b) = (self.index(a)?, self.index(b)?);
+        self.union_find.union(a, b);
+        Ok(())
+    }
+
+    /// Marks the e-class of `node` as a root
+    pub fn add_root(&mut self, node: &NodeId) -> Result<(), Error> {
+        let index = self.index(node)?;
+        self.roots.push(index);
+        Ok(())
+    }
+
+    /// Creates the e-graph, with the root e-classes in the order they were first added.
+    ///
+    /// The e-graph is not congruence-closed: after `a` and `b` are merged, `(f a)` and `(f b)` are still in
+    /// different e-classes. Call [`EGraph::rebuild`] on the result to merge them.
+    pub fn build(mut self) -> EGraph {
+        let mut egraph = EGraph::default();
+        for (index, (op, children)) in self.nodes.iter().enumerate() {
+            let eclass = self.union_find.find(index).to_string().into();
+            let node = Node {
+                op: op.clone(),
+                children: children
+                    .iter()
+                    .map(|child| child.to_string().into())
+                    .collect(),
+                eclass,
+                cost: crate::one(),
+                subsumed: false,
+            };
+            egraph.add_node(index.to_string(), node);
+        }
+        for &root in &self.roots {
+            let class_id = ClassId::from(self.union_find.find(root).to_string());
+            if !egraph.root_eclasses.contains(&class_id) {
+                egraph.root_eclasses.push(class_id);
+            }
+        }
+        egraph
+    }
+
+    fn index(&self, node: &NodeId) -> Result<usize, Error> {
+        node.as_ref()
+            .parse()
+            .ok()
+            .filter(|&index| index < self.nodes.len())
+            .ok_or_else(|| Error::MissingNode(node.clone()))
+    }
+
+    fn add_sexp(&mut self, sexp: &Sexp) -> Result<usize, Error> {
+        let (op, children) = match sexp {
+            Sexp::Atom(op) => (op.clone(), vec![]),
+            Sexp::List(items, start) => match items.split_first() {
+                Some((Sexp::Atom(op), args)) => {
+                    let children = args
+                        .iter()
+                        .map(|arg| self.add_sexp(arg))
+                        .collect::<Result<_, _>>()?;
+                    (op.clone(), children)
+                }
+                Some((Sexp::List(_, start), _)) => {
+                    return Err(parse_error(*start, "expected an op"))
+                }
+                None => return Err(parse_error(*start, "empty list")),
+            },
+        };
+        let (index, inserted) = self.nodes.insert_full((op, children));
+        if inserted {
+            self.union_find.make_set();
+        }
+        Ok(index)
+    }
+}
+
+impl EGraph {
+    /// Builds an e-graph from s-expressions, like `(+ x 0) (= (+ x 0) x)`, with a [`SexpBuilder`].
+    ///
+    /// A top-level form `(= a b ...)` puts all of its terms in one e-class, and every other top-level term
+    /// becomes a root e-class. `=` is an ordinary op anywhere else.
+    ///
+    /// Like [`SexpBuilder::build`], the result is not congruence-closed, so `(f a) (f b) (= a b)` leaves `(f a)`
+    /// and `(f b)` in different e-classes until [`EGraph::rebuild`] is called.
+    pub fn from_sexps(input: &str) -> Result<EGraph, Error> {
+        let mut builder = SexpBuilder::new();
+        for sexp in parse(input)? {
+            match sexp {
+                Sexp::List(items, start) if is_equality(&items) => {
+                    if items.len() < 3 {
+                        return Err(parse_error(start, "`=` needs at least two terms"));
+                    }
+                    let first = builder.add_sexp(&items[1])?;
+                    for term in &items[2..] {
+                        let index = builder.add_sexp(term)?;
+                        builder.union_find.union(first, index);
+                    }
+                }
+                term => {
+                    let index = builder.add_sexp(&term)?;
+                    builder.roots.push(index);
+                }
+            }
+        }
+        Ok(builder.build())
+    }
+}
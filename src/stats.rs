@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use crate::{Cost, EGraph};
+
+/// Summary statistics about an e-graph, returned by [`EGraph::stats`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub n_nodes: usize,
+    pub n_classes: usize,
+    pub n_roots: usize,
+    /// Nodes without children
+    pub n_leaves: usize,
+    pub n_subsumed: usize,
+    /// How many nodes use each op, in order of first appearance
+    pub op_counts: IndexMap<String, usize>,
+    /// How many e-classes have each number of nodes
+    pub class_sizes: BTreeMap<usize, usize>,
+    /// Largest number of children of any node
+    pub max_arity: usize,
+    /// Average number of children per node
+    pub mean_arity: f64,
+    /// Cost of the cheapest node, or `None` if there are no nodes
+    pub min_cost: Option<Cost>,
+    /// Cost of the most expensive node, or `None` if there are no nodes
+    pub max_cost: Option<Cost>,
+    /// Sum of the costs of all nodes
+    pub total_cost: Cost,
+    /// E-class and node counts for each [`ClassData::typ`](crate::ClassData::typ), with `None` for e-classes
+    /// without a type
+    pub types: IndexMap<Option<String>, TypeStats>,
+}
+
+/// E-class and node counts for a single type in [`Stats`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TypeStats {
+    pub n_classes: usize,
+    pub n_nodes: usize,
+}
+
+impl Stats {
+    /// Average cost of a node, or `None` if there are no nodes
+    pub fn mean_cost(&self) -> Option<f64> {
+        (self.n_nodes > 0).then(|| self.total_cost.into_inner() / self.n_nodes as f64)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes: {}", self.n_nodes)?;
+        writeln!(f, "classes: {}", self.n_classes)?;
+        writeln!(f, "roots: {}", self.n_roots)?;
+        writeln!(f, "leaves: {}", self.n_leaves)?;
+        writeln!(f, "subsumed: {}", self.n_subsumed)?;
+        writeln!(
+            f,
+            "arity: max {}, mean {:.2}",
+            self.max_arity, self.mean_arity
+        )?;
+        if let (Some(min), Some(max), Some(mean)) = (self.min_cost, self.max_cost, self.mean_cost())
+        {
+            writeln!(
+                f,
+                "cost: min {min}, max {max}, mean {mean:.2}, total {}",
+                self.total_cost
+            )?;
+        }
+        writeln!(f, "class sizes:")?;
+        for (size, count) in &self.class_sizes {
+            writeln!(f, "  {size}: {count}")?;
+        }
+        writeln!(f, "ops:")?;
+        for (op, count) in &self.op_counts {
+            writeln!(f, "  {op}: {count}")?;
+        }
+        writeln!(f, "types:")?;
+        for (typ, stats) in &self.types {
+            writeln!(
+                f,
+                "  {}: {} classes, {} nodes",
+                typ.as_deref().unwrap_or("<none>"),
+                stats.n_classes,
+                stats.n_nodes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl EGraph {
+    /// Computes summary statistics about the e-graph
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            n_nodes: self.nodes.len(),
+            n_classes: self.classes().len(),
+            n_roots: self.root_eclasses.len(),
+            ..Default::default()
+        };
+
+        let mut total_arity = 0;
+        for node in self.nodes.values() {
+            if node.is_leaf() {
+                stats.n_leaves += 1;
+            }
+            if node.subsumed {
+                stats.n_subsumed += 1;
+            }
+            *stats.op_counts.entry(node.op.clone()).or_default() += 1;
+            total_arity += node.children.len();
+            stats.max_arity = stats.max_arity.max(node.children.len());
+            stats.min_cost = Some(stats.min_cost.map_or(node.cost, |c| c.min(node.cost)));
+            stats.max_cost = Some(stats.max_cost.map_or(node.cost, |c| c.max(node.cost)));
+            stats.total_cost += node.cost;
+        }
+        if !self.nodes.is_empty() {
+            stats.mean_arity = total_arity as f64 / self.nodes.len() as f64;
+        }
+
+        for (class_id, class) in self.classes() {
+            *stats.class_sizes.entry(class.nodes.len()).or_default() += 1;
+            let typ = self
+                .class_data
+                .get(class_id)
+                .and_then(|data| data.typ.clone());
+            let type_stats = stats.types.entry(typ).or_default();
+            type_stats.n_classes += 1;
+            type_stats.n_nodes += class.nodes.len();
+        }
+        stats
+    }
+}
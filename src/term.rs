@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{ClassId, EGraph, Error, ExtractionResult, MISSING_ARG_VALUE};
+
+/// A term built from extracted nodes: an operator applied to child terms
+///
+/// Displays as an s-expression like `(f x (g y))`. Ops produced by [`EGraph::inline_leaves`], like `f(10, ·)`,
+/// display with each `·` replaced by the matching child, like `f(10, (g y))`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Term {
+    pub op: String,
+    pub children: Vec<Arc<Term>>,
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.children.is_empty() {
+            return write!(f, "{}", self.op);
+        }
+        if self.op.matches(MISSING_ARG_VALUE).count() == self.children.len() {
+            for (i, part) in self.op.split(MISSING_ARG_VALUE).enumerate() {
+                if i > 0 {
+                    write!(f, "{}", self.children[i - 1])?;
+                }
+                write!(f, "{part}")?;
+            }
+            return Ok(());
+        }
+        write!(f, "({}", self.op)?;
+        for child in &self.children {
+            write!(f, " {child}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl ExtractionResult {
+    /// Builds the chosen term for each of the e-graph's `root_eclasses`, in the same order.
+    ///
+    /// Terms for the same e-class are shared between all their uses.
+    /// Fails if a reachable e-class has no chosen node or the chosen nodes form a cycle.
+    pub fn terms(&self, egraph: &EGraph) -> Result<Vec<Arc<Term>>, Error> {
+        self.build_terms(egraph, &egraph.root_eclasses)
+    }
+
+    /// Builds the chosen term for a single e-class
+    pub fn term(&self, egraph: &EGraph, class_id: &ClassId) -> Result<Arc<Term>, Error> {
+        let mut terms = self.build_terms(egraph, std::slice::from_ref(class_id))?;
+        Ok(terms.remove(0))
+    }
+
+    fn build_terms(&self, egraph: &EGraph, roots: &[ClassId]) -> Result<Vec<Arc<Term>>, Error> {
+        let mut terms: HashMap<&ClassId, Arc<Term>> = HashMap::new();
+        // Children come first, so every child term is built before it is needed
+        for class_id in self.topological_order(egraph, roots)? {
+            let node = &egraph[&self.choices[class_id]];
+            let term = Term {
+                op: node.op.clone(),
+                children: node
+                    .children
+                    .iter()
+                    .map(|child| terms[egraph.nid_to_cid(child)].clone())
+                    .collect(),
+            };
+            terms.insert(class_id, Arc::new(term));
+        }
+        Ok(roots.iter().map(|root| terms[root].clone()).collect())
+    }
+}
@@ -0,0 +1,103 @@
+use indexmap::map::Entry;
+
+use crate::{ClassData, ClassId, EGraph, Error};
+
+/// How class data is combined when two e-classes are merged, for fields that both of them set
+///
+/// The default methods keep the value of the e-class that survives the merge. Policies take `&mut self`,
+/// so they can also record the conflicts they see.
+pub trait MergePolicy {
+    /// Returns the `typ` of the merged e-class, given the `typ` of the surviving e-class and of the one merged
+    /// into it
+    fn merge_typ(&mut self, class: &ClassId, kept: String, merged: String) -> String {
+        let _ = (class, merged);
+        kept
+    }
+
+    /// Returns the value of `key` in the `extra` of the merged e-class, given the values of the surviving
+    /// e-class and of the one merged into it
+    fn merge_extra(&mut self, class: &ClassId, key: &str, kept: String, merged: String) -> String {
+        let _ = (class, key, merged);
+        kept
+    }
+}
+
+/// A [`MergePolicy`] that always keeps the class data of the surviving e-class
+#[derive(Debug, Default, Clone, Copy)]
+pub struct KeepFirst;
+
+impl MergePolicy for KeepFirst {}
+
+impl EGraph {
+    /// Merges e-class `b` into e-class `a`, keeping the class data of `a` where both set a field.
+    ///
+    /// See [`EGraph::union_with`] for details.
+    pub fn union(&mut self, a: &ClassId, b: &ClassId) -> Result<(), Error> {
+        self.union_with(a, b, &mut KeepFirst)
+    }
+
+    /// Merges e-class `b` into e-class `a`, combining their class data with `policy`.
+    ///
+    /// All nodes of `b` move to `a`, the class data of `b` is merged into that of `a`, and `b` is replaced by
+    /// `a` in `root_eclasses`, without repeating `a`.
+    /// Fails without changing anything if either e-class has no nodes.
+    pub fn union_with(
+        &mut self,
+        a: &ClassId,
+        b: &ClassId,
+        policy: &mut impl MergePolicy,
+    ) -> Result<(), Error> {
+        self.refresh_caches();
+        self.get_class(a)?;
+        if a == b {
+            return Ok(());
+        }
+        let moved = self.get_class(b)?.nodes.clone();
+        for node_id in &moved {
+            self.nodes[node_id].eclass = a.clone();
+        }
+        self.merge_class_data(a, b, policy);
+
+        let mut seen_a = false;
+        self.root_eclasses.retain_mut(|root| {
+            if root == b {
+                *root = a.clone();
+            }
+            root != a || !std::mem::replace(&mut seen_a, true)
+        });
+
+        self.invalidate_caches();
+        Ok(())
+    }
+
+    /// Moves the class data of `merged` into that of `kept`, using `policy` for fields that both set
+    pub(crate) fn merge_class_data(
+        &mut self,
+        kept: &ClassId,
+        merged: &ClassId,
+        policy: &mut impl MergePolicy,
+    ) {
+        let Some(data) = self.class_data.shift_remove(merged) else {
+            return;
+        };
+        let kept_data = match self.class_data.entry(kept.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(data);
+                return;
+            }
+        };
+        let ClassData { typ, extra } = data;
+        kept_data.typ = match (kept_data.typ.take(), typ) {
+            (Some(kept_typ), Some(typ)) => Some(policy.merge_typ(kept, kept_typ, typ)),
+            (kept_typ, typ) => kept_typ.or(typ),
+        };
+        for (key, value) in extra {
+            let merged_value = match kept_data.extra.remove(&key) {
+                Some(kept_value) => policy.merge_extra(kept, &key, kept_value, value),
+                None => value,
+            };
+            kept_data.extra.insert(key, merged_value);
+        }
+    }
+}
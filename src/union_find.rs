@@ -0,0 +1,32 @@
+/// A union-find over the indices `0..len`, where the smallest index in a set is its representative
+#[derive(Debug, Default, Clone)]
+pub(crate) struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    /// Adds a new singleton set and returns its index
+    pub(crate) fn make_set(&mut self) -> usize {
+        let id = self.parents.len();
+        self.parents.push(id);
+        id
+    }
+
+    /// Returns the representative of the set containing `id`
+    pub(crate) fn find(&mut self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            // Path halving
+            self.parents[id] = self.parents[self.parents[id]];
+            id = self.parents[id];
+        }
+        id
+    }
+
+    /// Merges the sets containing `a` and `b`, returning the representative of the merged set
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.find(a), self.find(b));
+        let (root, child) = if a <= b { (a, b) } else { (b, a) };
+        self.parents[child] = root;
+        root
+    }
+}
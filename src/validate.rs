@@ -0,0 +1,134 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{ClassId, Cost, EGraph, NodeId};
+
+/// A structural problem found by [`EGraph::validate`].
+///
+/// Repeated children within a single node (like `f(x, x)`) are valid and are not reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// A node lists a child that is not in the e-graph.
+    DanglingChild {
+        node: NodeId,
+        position: usize,
+        child: NodeId,
+    },
+    /// An entry in `root_eclasses` names a class that has no nodes.
+    MissingRootClass(ClassId),
+    /// The same class appears more than once in `root_eclasses`.
+    DuplicateRootClass(ClassId),
+    /// A key in `class_data` names a class that has no nodes.
+    OrphanClassData(ClassId),
+    /// A node is the only member of its e-class, and that e-class is not a root,
+    /// has no class data and is not the class of any child.
+    IsolatedNode { node: NodeId, class: ClassId },
+    /// A node has a cost below zero.
+    NegativeCost { node: NodeId, cost: Cost },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::DanglingChild {
+                node,
+                position,
+                child,
+            } => write!(
+                f,
+                "node {node} has missing child {child} at position {position}"
+            ),
+            ValidationError::MissingRootClass(class) => {
+                write!(f, "root e-class {class} has no nodes")
+            }
+            ValidationError::DuplicateRootClass(class) => {
+                write!(f, "root e-class {class} is listed more than once")
+            }
+            ValidationError::OrphanClassData(class) => {
+                write!(f, "class data given for e-class {class} which has no nodes")
+            }
+            ValidationError::IsolatedNode { node, class } => {
+                write!(f, "node {node} is the only reference to e-class {class}")
+            }
+            ValidationError::NegativeCost { node, cost } => {
+                write!(f, "node {node} has negative cost {cost}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl EGraph {
+    /// Checks the e-graph for structural problems, like children that point to missing nodes.
+    ///
+    /// Returns every problem found, in the order of `nodes`, then `root_eclasses`, then `class_data`.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+
+        let mut class_sizes: HashMap<&ClassId, usize> = HashMap::new();
+        for node in self.nodes.values() {
+            *class_sizes.entry(&node.eclass).or_default() += 1;
+        }
+
+        let mut referenced_classes = HashSet::new();
+        for (node_id, node) in &self.nodes {
+            for (position, child) in node.children.iter().enumerate() {
+                match self.nodes.get(child) {
+                    Some(child_node) => {
+                        referenced_classes.insert(&child_node.eclass);
+                    }
+                    None => errors.push(ValidationError::DanglingChild {
+                        node: node_id.clone(),
+                        position,
+                        child: child.clone(),
+                    }),
+                }
+            }
+            if node.cost < Cost::default() {
+                errors.push(ValidationError::NegativeCost {
+                    node: node_id.clone(),
+                    cost: node.cost,
+                });
+            }
+        }
+
+        let roots: HashSet<&ClassId> = self.root_eclasses.iter().collect();
+        for (node_id, node) in &self.nodes {
+            let class = &node.eclass;
+            if class_sizes[class] == 1
+                && !referenced_classes.contains(class)
+                && !roots.contains(class)
+                && !self.class_data.contains_key(class)
+            {
+                errors.push(ValidationError::IsolatedNode {
+                    node: node_id.clone(),
+                    class: class.clone(),
+                });
+            }
+        }
+
+        let mut seen_roots = HashSet::new();
+        for root in &self.root_eclasses {
+            if !class_sizes.contains_key(root) {
+                errors.push(ValidationError::MissingRootClass(root.clone()));
+            }
+            if !seen_roots.insert(root) {
+                errors.push(ValidationError::DuplicateRootClass(root.clone()));
+            }
+        }
+
+        for class in self.class_data.keys() {
+            if !class_sizes.contains_key(class) {
+                errors.push(ValidationError::OrphanClassData(class.clone()));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
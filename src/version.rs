@@ -0,0 +1,103 @@
+use crate::EGraph;
+#[cfg(feature = "serde")]
+use crate::{ClassData, ClassId, Node, NodeId};
+#[cfg(feature = "serde")]
+use indexmap::IndexMap;
+
+/// The version of the serialized e-graph format written by this crate
+///
+/// Documents without a `version` key are version 0, which covers everything written by 0.3 and earlier.
+/// Reading a document with a version newer than this fails.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Upgrades an e-graph read from version `i` to version `i + 1`
+#[cfg(feature = "serde")]
+const MIGRATIONS: [fn(&mut EGraph); FORMAT_VERSION as usize] = [
+    // Version 1 only added the `version` key. Fields added before it, like `subsumed` and arbitrary
+    // `class_data`, already default when missing.
+    |_| {},
+];
+
+/// The format version an e-graph was read from, always serialized as [`FORMAT_VERSION`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FormatVersion(pub(crate) u32);
+
+impl FormatVersion {
+    /// The version of documents without a `version` key
+    #[cfg(feature = "serde")]
+    pub(crate) fn legacy() -> Self {
+        FormatVersion(0)
+    }
+}
+
+impl Default for FormatVersion {
+    fn default() -> Self {
+        FormatVersion(FORMAT_VERSION)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FormatVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(FORMAT_VERSION)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FormatVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = u32::deserialize(deserializer)?;
+        if version > FORMAT_VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "e-graph format version {version} is newer than the latest version {FORMAT_VERSION} \
+                 supported by this version of egraph-serialize"
+            )));
+        }
+        Ok(FormatVersion(version))
+    }
+}
+
+/// An e-graph as it appears in a document, before it is upgraded to the current format version
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+pub(crate) struct RawEGraph {
+    #[serde(default = "FormatVersion::legacy")]
+    version: FormatVersion,
+    nodes: IndexMap<NodeId, Node>,
+    #[serde(default)]
+    root_eclasses: Vec<ClassId>,
+    #[serde(default)]
+    class_data: IndexMap<ClassId, ClassData>,
+}
+
+#[cfg(feature = "serde")]
+impl From<RawEGraph> for EGraph {
+    fn from(raw: RawEGraph) -> Self {
+        let mut egraph = EGraph {
+            version: raw.version,
+            nodes: raw.nodes,
+            root_eclasses: raw.root_eclasses,
+            class_data: raw.class_data,
+            ..Default::default()
+        };
+        egraph.migrate();
+        egraph
+    }
+}
+
+impl EGraph {
+    /// The format version this e-graph was read from, or [`FORMAT_VERSION`] if it was built in memory
+    pub fn format_version(&self) -> u32 {
+        self.version.0
+    }
+
+    /// Upgrades an e-graph read from an older format version to the current one
+    ///
+    /// Every way of deserializing an [`EGraph`] does this already.
+    #[cfg(feature = "serde")]
+    pub(crate) fn migrate(&mut self) {
+        for migration in &MIGRATIONS[self.version.0 as usize..] {
+            migration(self);
+        }
+    }
+}
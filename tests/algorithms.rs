@@ -0,0 +1,309 @@
+use egraph_serialize::*;
+
+mod common;
+use common::{class_data, node};
+
+#[test]
+fn test_retain_reachable() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x", node("x", &[], "X"));
+    egraph.add_node("y", node("y", &[], "X"));
+    egraph.add_node("f", node("f", &["x"], "F"));
+    egraph.add_node("g", node("g", &["f"], "G"));
+    egraph.add_node("h", node("h", &[], "H"));
+    for class in ["F", "G", "H"] {
+        egraph.class_data.insert(
+            class.into(),
+            ClassData {
+                typ: Some("T".into()),
+                extra: Default::default(),
+            },
+        );
+    }
+    egraph.root_eclasses = vec!["F".into()];
+
+    let stats = egraph.retain_reachable();
+    assert_eq!(
+        stats,
+        RetainStats {
+            removed_nodes: 2,
+            removed_classes: 2,
+            removed_class_data: 2,
+        }
+    );
+    let mut remaining: Vec<_> = egraph.nodes.keys().map(|id| id.to_string()).collect();
+    remaining.sort();
+    assert_eq!(remaining, ["f", "x", "y"]);
+    assert_eq!(egraph.class_data.len(), 1);
+    assert_eq!(egraph.classes().len(), 2);
+}
+
+#[test]
+fn test_retain_reachable_fixtures() {
+    for entry in glob::glob("tests/*.json").unwrap() {
+        let mut egraph = EGraph::from_json_file(entry.unwrap()).unwrap();
+        if egraph.root_eclasses.is_empty() {
+            continue;
+        }
+        let result = egraph.extract_bottom_up();
+        let tree_cost = result.tree_cost(&egraph, &egraph.root_eclasses);
+        egraph.retain_reachable();
+        // Pruning doesn't change the best extraction from the roots
+        let result = egraph.extract_bottom_up();
+        assert_eq!(result.tree_cost(&egraph, &egraph.root_eclasses), tree_cost);
+        assert_eq!(egraph.retain_reachable(), RetainStats::default());
+    }
+}
+
+#[test]
+fn test_subgraph() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x", node("x", &[], "X"));
+    egraph.add_node("f", node("f", &["x"], "F"));
+    egraph.add_node("g", node("g", &["f"], "G"));
+    egraph.add_node("h", node("h", &["g"], "H"));
+    egraph.add_node("k", node("k", &["h"], "K"));
+
+    let slice = egraph.subgraph(&["G".into()], 1);
+    assert_eq!(slice.root_eclasses, vec![ClassId::from("G")]);
+    let mut kept: Vec<_> = slice.nodes.keys().map(|id| id.to_string()).collect();
+    kept.sort();
+    assert_eq!(kept, ["X·", "f", "g"]);
+    // The edge from `f` to `x` leaves the slice, so it points to a stub of `X` instead
+    assert_eq!(slice[&NodeId::from("f")].children, vec!["X·".into()]);
+    let stub = &slice[&NodeId::from("X·")];
+    assert_eq!(stub.op, MISSING_ARG_VALUE);
+    assert_eq!(stub.eclass, "X".into());
+    assert!(stub.subsumed);
+    assert_eq!(slice.validate(), Ok(()));
+
+    let slice = egraph.subgraph_with_parents(&["G".into()], 0, 2);
+    let mut kept: Vec<_> = slice.nodes.keys().map(|id| id.to_string()).collect();
+    kept.sort();
+    assert_eq!(kept, ["F·", "g", "h", "k"]);
+}
+
+#[test]
+fn test_subgraph_keeps_arity() {
+    let mut egraph = EGraph::from_sexps("(f x (z y)) (g y)").unwrap();
+    let f = egraph.root_eclasses[0].clone();
+    egraph.class_data.insert("1".into(), class_data("T", &[]));
+
+    let slice = egraph.subgraph(std::slice::from_ref(&f), 1);
+    for (node_id, node) in &egraph.nodes {
+        if let Some(sliced) = slice.nodes.get(node_id) {
+            assert_eq!(sliced.children.len(), node.children.len());
+        }
+    }
+    // `y` is two hops away, so `z` points to a stub of its e-class, which keeps its class data
+    let z = &slice[&NodeId::from("2")];
+    let stub = &slice[&z.children[0]];
+    assert_eq!(stub.eclass, "1".into());
+    assert!(stub.subsumed);
+    assert_eq!(
+        slice.class_data[&ClassId::from("1")].typ.as_deref(),
+        Some("T")
+    );
+    // The stub can't be extracted, so neither can the terms that use it
+    assert!(!slice.extract_bottom_up().choices.contains_key(&f));
+}
+
+#[test]
+fn test_subgraph_stub_ids() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("X·", node("x", &[], "X"));
+    egraph.add_node("q", node("q", &[], "X·"));
+    egraph.add_node("f", node("f", &["X·", "q"], "F"));
+    egraph.root_eclasses = vec!["F".into()];
+
+    let slice = egraph.subgraph(&egraph.root_eclasses, 0);
+    assert_eq!(
+        slice[&NodeId::from("f")].children,
+        vec!["X··".into(), "X···".into()]
+    );
+    assert_eq!(slice[&NodeId::from("X··")].eclass, "X".into());
+    assert_eq!(slice[&NodeId::from("X···")].eclass, "X·".into());
+    // Stubs come after the kept nodes, in the order they are first used
+    let ids: Vec<&NodeId> = slice.nodes.keys().collect();
+    assert_eq!(ids, [&"f".into(), &"X··".into(), &"X···".into()]);
+}
+
+#[test]
+fn test_cycles() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x", node("x", &[], "X"));
+    egraph.add_node("f", node("f", &["x"], "F"));
+    egraph.add_node("g", node("g", &["f"], "G"));
+    egraph.add_node("h", node("h", &["g"], "F"));
+    egraph.add_node("k", node("k", &["k"], "K"));
+    egraph.add_node("k2", node("k2", &["f"], "K"));
+
+    let sccs = egraph.class_sccs();
+    assert_eq!(sccs.len(), 3);
+    // Children come before their parents
+    assert_eq!(sccs[0], vec![ClassId::from("X")]);
+    assert_eq!(sccs[1].len(), 2);
+    assert_eq!(sccs[2], vec![ClassId::from("K")]);
+
+    let report = egraph.cycles();
+    assert!(!report.is_acyclic());
+    assert_eq!(report.self_loops, vec![ClassId::from("K")]);
+    assert_eq!(report.cyclic_classes().count(), 3);
+    let mut cycle_nodes: Vec<_> = report.cycle_nodes.iter().map(|id| id.to_string()).collect();
+    cycle_nodes.sort();
+    assert_eq!(cycle_nodes, ["g", "h", "k"]);
+
+    let tiny = EGraph::from_json_file("tests/tiny.json").unwrap();
+    assert!(tiny.cycles().is_acyclic());
+}
+
+#[test]
+fn test_stats() {
+    let egraph = EGraph::from_json_file("tests/tiny.json").unwrap();
+    let stats = egraph.stats();
+    assert_eq!(stats.n_nodes, 3);
+    assert_eq!(stats.n_classes, 2);
+    assert_eq!(stats.n_roots, 0);
+    assert_eq!(stats.n_leaves, 2);
+    assert_eq!(stats.n_subsumed, 0);
+    assert_eq!(stats.op_counts["foo"], 1);
+    assert_eq!(stats.class_sizes.get(&1), Some(&1));
+    assert_eq!(stats.class_sizes.get(&2), Some(&1));
+    assert_eq!(stats.max_arity, 2);
+    assert_eq!(stats.min_cost, Some(Cost::new(1.0).unwrap()));
+    assert_eq!(stats.max_cost, Some(Cost::new(6.0).unwrap()));
+    assert_eq!(stats.mean_cost(), Some(8.0 / 3.0));
+    assert_eq!(
+        stats.types[&Some("foobar".to_string())],
+        TypeStats {
+            n_classes: 1,
+            n_nodes: 1,
+        }
+    );
+    assert_eq!(stats.types[&None].n_nodes, 2);
+    assert!(stats.to_string().starts_with("nodes: 3\n"));
+}
+
+#[test]
+fn test_rebuild() {
+    let mut egraph = EGraph::from_sexps("(g (f a)) (g (f b)) (= a b)").unwrap();
+    egraph.nodes_mut()[&NodeId::from("4")].cost = Cost::new(0.5).unwrap();
+    let data = |typ: Option<&str>, key: &str, value: &str| ClassData {
+        typ: typ.map(str::to_string),
+        extra: [(key.to_string(), value.to_string())].into(),
+    };
+    egraph
+        .class_data
+        .insert("1".into(), data(None, "source", "left"));
+    egraph
+        .class_data
+        .insert("4".into(), data(Some("T"), "source", "right"));
+
+    let report = egraph.rebuild().unwrap();
+    assert_eq!(
+        report.merged_classes,
+        [("4".into(), "1".into()), ("5".into(), "2".into())].into()
+    );
+    assert_eq!(
+        report.merged_nodes,
+        [("4".into(), "1".into()), ("5".into(), "2".into())].into()
+    );
+    assert_eq!(egraph.nodes.len(), 4);
+    assert_eq!(egraph[&NodeId::from("1")].cost, Cost::new(0.5).unwrap());
+    assert_eq!(egraph.root_eclasses, vec!["2".into()]);
+    assert_eq!(egraph.class_data.len(), 1);
+    assert_eq!(
+        egraph.class_data[&ClassId::from("1")],
+        data(Some("T"), "source", "left")
+    );
+    assert_eq!(egraph[&ClassId::from("0")].nodes.len(), 2);
+    assert!(egraph.validate().is_ok());
+
+    assert!(egraph.rebuild().unwrap().is_empty());
+}
+
+#[test]
+fn test_rebuild_cascades() {
+    // Merging a and b makes f(a) and f(b) congruent, which in turn makes g(f(a)) and g(f(b)) congruent
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("b", node("a", &[], "B"));
+    egraph.add_node("fa", node("f", &["a"], "FA"));
+    egraph.add_node("fb", node("f", &["b"], "FB"));
+    egraph.add_node("gfa", node("g", &["fa"], "G"));
+    egraph.add_node("gfb", node("g", &["fb"], "H"));
+    egraph.add_node("dangling", node("h", &["missing"], "H"));
+    let before = egraph.clone();
+    assert_eq!(egraph.rebuild(), Err(Error::MissingNode("missing".into())));
+    assert_eq!(egraph, before);
+
+    egraph.nodes_mut().swap_remove(&NodeId::from("dangling"));
+    let report = egraph.rebuild().unwrap();
+    assert_eq!(report.merged_classes.len(), 3);
+    assert_eq!(egraph.nodes.len(), 3);
+    assert_eq!(egraph.classes().len(), 3);
+    assert_eq!(egraph[&NodeId::from("gfa")].children, vec!["fa".into()]);
+}
+
+#[test]
+fn test_rebuild_fixtures() {
+    for entry in glob::glob("tests/*.json").unwrap() {
+        let mut egraph = EGraph::from_json_file(entry.unwrap()).unwrap();
+        let classes = egraph.classes().len();
+        let report = egraph.rebuild().unwrap();
+        assert_eq!(
+            egraph.classes().len(),
+            classes - report.merged_classes.len()
+        );
+        assert!(egraph.rebuild().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn test_dedup_nodes() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x1", node("x", &[], "X"));
+    egraph.add_node("x2", node("x", &[], "X"));
+    egraph.add_node("y", node("x", &[], "Y"));
+    egraph.add_node("f1", node("f", &["x1"], "F"));
+    egraph.add_node("f2", node("f", &["x2"], "F"));
+    egraph.add_node("h0", node("h", &["f2"], "H"));
+    egraph.add_node("h1", node("h", &["f1"], "H"));
+    egraph.add_node("h2", node("h", &["f1"], "H"));
+    egraph.root_eclasses = vec!["H".into()];
+    egraph.nodes_mut()[&NodeId::from("x2")].cost = Cost::new(0.5).unwrap();
+    egraph.nodes_mut()[&NodeId::from("f1")].subsumed = true;
+
+    let merged = egraph.dedup_nodes();
+    assert_eq!(
+        merged,
+        [
+            ("x2".into(), "x1".into()),
+            ("h2".into(), "h0".into()),
+            ("f2".into(), "f1".into()),
+            ("h1".into(), "h0".into()),
+        ]
+        .into()
+    );
+    let ids: Vec<&str> = egraph.nodes.keys().map(|id| id.as_ref()).collect();
+    assert_eq!(ids, ["x1", "y", "f1", "h0"]);
+    assert_eq!(egraph[&NodeId::from("x1")].cost, Cost::new(0.5).unwrap());
+    assert!(!egraph[&NodeId::from("f1")].subsumed);
+    assert_eq!(egraph[&NodeId::from("h0")].children, vec!["f1".into()]);
+    assert_eq!(egraph[&ClassId::from("F")].nodes.len(), 1);
+
+    assert!(egraph.dedup_nodes().is_empty());
+}
+
+#[test]
+fn test_dedup_nodes_fixtures() {
+    for entry in glob::glob("tests/*.json").unwrap() {
+        let mut egraph = EGraph::from_json_file(entry.unwrap()).unwrap();
+        let nodes = egraph.nodes.len();
+        let classes = egraph.classes().len();
+        let merged = egraph.dedup_nodes();
+        assert_eq!(egraph.nodes.len(), nodes - merged.len());
+        assert_eq!(egraph.classes().len(), classes);
+        assert!(egraph.dedup_nodes().is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+use egraph_serialize::*;
+
+mod common;
+use common::{class_data, node};
+
+#[test]
+fn test_classes_follow_mutations() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("f", node("f", &["a"], "F"));
+    assert_eq!(egraph.classes().len(), 2);
+
+    egraph.add_node("b", node("b", &[], "A"));
+    assert_eq!(egraph[&ClassId::from("A")].nodes.len(), 2);
+
+    egraph.nodes_mut()[&NodeId::from("b")].eclass = "B".into();
+    assert_eq!(egraph[&ClassId::from("A")].nodes.len(), 1);
+    assert_eq!(egraph[&ClassId::from("B")].nodes.len(), 1);
+
+    egraph.inline_leaves();
+    assert_eq!(egraph.classes().len(), 2);
+    assert!(egraph.get_class(&"A".into()).is_err());
+}
+
+#[test]
+fn test_parents_follow_mutations() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("f", node("f", &["a", "a"], "F"));
+    assert_eq!(
+        egraph.class_parents(&"A".into()),
+        [("f".into(), 0), ("f".into(), 1)]
+    );
+    assert!(egraph.class_parents(&"F".into()).is_empty());
+
+    egraph.add_node("g", node("g", &["f", "missing"], "G"));
+    assert_eq!(egraph.class_parents(&"F".into()), [("g".into(), 0)]);
+    assert_eq!(egraph.parents().len(), 2);
+
+    egraph.nodes_mut()[&NodeId::from("g")].children = vec!["a".into()];
+    assert!(egraph.class_parents(&"F".into()).is_empty());
+    assert_eq!(egraph.class_parents(&"A".into()).len(), 3);
+}
+
+#[test]
+fn test_union() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("b", node("b", &[], "B"));
+    egraph.add_node("f", node("f", &["b"], "F"));
+    egraph.root_eclasses = vec!["B".into(), "F".into(), "A".into()];
+    egraph
+        .class_data
+        .insert("B".into(), class_data("T", &[("k", "v")]));
+    assert_eq!(egraph[&ClassId::from("B")].nodes.len(), 1);
+
+    egraph.union(&"A".into(), &"B".into()).unwrap();
+    assert_eq!(egraph[&ClassId::from("A")].nodes.len(), 2);
+    assert!(egraph.get_class(&"B".into()).is_err());
+    assert_eq!(egraph.root_eclasses, vec!["A".into(), "F".into()]);
+    assert_eq!(
+        egraph.class_data[&ClassId::from("A")],
+        class_data("T", &[("k", "v")])
+    );
+    assert_eq!(egraph.class_parents(&"A".into()), [("f".into(), 0)]);
+
+    let before = egraph.clone();
+    assert_eq!(
+        egraph.union(&"A".into(), &"B".into()),
+        Err(Error::MissingClass("B".into()))
+    );
+    egraph.union(&"A".into(), &"A".into()).unwrap();
+    assert_eq!(egraph, before);
+}
+
+#[test]
+fn test_union_with_policy() {
+    #[derive(Default)]
+    struct Conflicts(Vec<String>);
+
+    impl MergePolicy for Conflicts {
+        fn merge_typ(&mut self, _class: &ClassId, kept: String, merged: String) -> String {
+            self.0.push(format!("{kept}/{merged}"));
+            merged
+        }
+
+        fn merge_extra(
+            &mut self,
+            _class: &ClassId,
+            key: &str,
+            kept: String,
+            merged: String,
+        ) -> String {
+            self.0.push(key.to_string());
+            kept + &merged
+        }
+    }
+
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("b", node("b", &[], "B"));
+    egraph
+        .class_data
+        .insert("A".into(), class_data("S", &[("k", "1"), ("a", "2")]));
+    egraph
+        .class_data
+        .insert("B".into(), class_data("T", &[("k", "3"), ("b", "4")]));
+
+    let mut conflicts = Conflicts::default();
+    egraph
+        .union_with(&"A".into(), &"B".into(), &mut conflicts)
+        .unwrap();
+    assert_eq!(conflicts.0, ["S/T", "k"]);
+    assert_eq!(
+        egraph.class_data[&ClassId::from("A")],
+        class_data("T", &[("k", "13"), ("a", "2"), ("b", "4")])
+    );
+}
+
+#[test]
+fn test_inline_leaves_after_direct_edit() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("f", node("f", &["a"], "F"));
+    assert_eq!(egraph.class_parents(&"A".into()).len(), 1);
+
+    // Edits through the public field bypass the cached parent index
+    egraph.nodes.insert("g".into(), node("g", &["a"], "G"));
+    egraph.root_eclasses = vec!["F".into(), "G".into()];
+    assert_eq!(egraph.inline_leaves(), 1);
+    assert_eq!(egraph[&NodeId::from("f")].op, "f(a)");
+    assert_eq!(egraph[&NodeId::from("g")].op, "g(a)");
+    assert!(egraph[&NodeId::from("g")].children.is_empty());
+    assert!(egraph.validate().is_ok());
+}
+
+#[test]
+fn test_split_classes_after_direct_edit() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("b", node("b", &[], "A"));
+    egraph.add_node("f", node("f", &["a"], "F"));
+    assert_eq!(egraph.class_parents(&"A".into()).len(), 1);
+
+    egraph.nodes.insert("g".into(), node("g", &["b"], "G"));
+    egraph.root_eclasses = vec!["F".into(), "G".into()];
+    egraph.split_classes(|node_id, _| node_id.as_ref() == "a");
+    // Both parents of A now point at the copy of it that only holds `b`
+    assert_eq!(
+        egraph[&NodeId::from("g")].children,
+        egraph[&NodeId::from("f")].children
+    );
+    assert!(egraph.validate().is_ok());
+}
+
+#[test]
+fn test_union_after_direct_edit() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("b", node("b", &[], "B"));
+    egraph.add_node("c", node("c", &[], "C"));
+    assert_eq!(egraph[&ClassId::from("B")].nodes.len(), 1);
+
+    // Moving `c` into B by hand isn't seen by the cached classes
+    egraph.nodes[&NodeId::from("c")].eclass = "B".into();
+    egraph.union(&"A".into(), &"B".into()).unwrap();
+    assert_eq!(egraph.classes().len(), 1);
+    assert_eq!(egraph[&ClassId::from("A")].nodes.len(), 3);
+}
+
+#[test]
+fn test_retain_reachable_after_direct_edit() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x", node("x", &[], "X"));
+    egraph.add_node("f", node("f", &["x"], "F"));
+    egraph.add_node("g", node("g", &[], "G"));
+    egraph.root_eclasses = vec!["F".into()];
+    assert_eq!(egraph.classes().len(), 3);
+
+    // Removing `g` by hand isn't seen by the cached classes
+    egraph.nodes.swap_remove(&NodeId::from("g"));
+    let stats = egraph.retain_reachable();
+    assert_eq!(stats.removed_nodes, 0);
+    assert_eq!(stats.removed_classes, 0);
+    assert_eq!(egraph.nodes.len(), 2);
+}
@@ -0,0 +1,29 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use egraph_serialize::*;
+
+/// A node with a cost of one
+pub fn node(op: &str, children: &[&str], eclass: &str) -> Node {
+    node_with_cost(op, children, eclass, 1.0)
+}
+
+pub fn node_with_cost(op: &str, children: &[&str], eclass: &str, cost: f64) -> Node {
+    Node {
+        op: op.to_string(),
+        children: children.iter().map(|&c| c.into()).collect(),
+        eclass: eclass.into(),
+        cost: Cost::new(cost).unwrap(),
+        subsumed: false,
+    }
+}
+
+pub fn class_data(typ: &str, extra: &[(&str, &str)]) -> ClassData {
+    ClassData {
+        typ: Some(typ.to_string()),
+        extra: extra
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    }
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "egg")]
+
+use egg::{Id, Language, RecExpr, SymbolLang};
+use egraph_serialize::*;
+
+#[test]
+fn test_from_egg() {
+    let mut egg_egraph = egg::EGraph::<SymbolLang, ()>::default();
+    let a_plus_b = egg_egraph.add_expr(&"(+ a b)".parse::<RecExpr<SymbolLang>>().unwrap());
+    let b_plus_a = egg_egraph.add_expr(&"(+ b a)".parse::<RecExpr<SymbolLang>>().unwrap());
+    egg_egraph.union(a_plus_b, b_plus_a);
+    egg_egraph.rebuild();
+
+    let egraph = EGraph::from(&egg_egraph);
+    assert_eq!(egraph.nodes.len(), 4);
+    assert_eq!(egraph.classes().len(), 3);
+    assert!(egraph.root_eclasses.is_empty());
+
+    let egraph = EggConverter::new(&egg_egraph)
+        .with_cost(|node| Cost::new(node.children.len() as f64).unwrap())
+        .with_class_type(|_| Some("Math".to_string()))
+        .with_roots([a_plus_b])
+        .convert();
+    let root = &egraph.root_eclasses[0];
+    assert_eq!(egraph[root].nodes.len(), 2);
+    assert_eq!(egraph.validate(), Ok(()));
+    assert_eq!(egraph.class_data[root].typ.as_deref(), Some("Math"));
+
+    let result = egraph.extract_bottom_up();
+    assert_eq!(
+        result.tree_cost(&egraph, &egraph.root_eclasses),
+        Cost::new(2.0).unwrap()
+    );
+}
+
+/// A language without a `Display` impl
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Numbered {
+    op: u32,
+    children: Vec<Id>,
+}
+
+impl Language for Numbered {
+    type Discriminant = u32;
+
+    fn discriminant(&self) -> u32 {
+        self.op
+    }
+
+    fn matches(&self, other: &Self) -> bool {
+        self.op == other.op && self.children.len() == other.children.len()
+    }
+
+    fn children(&self) -> &[Id] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut [Id] {
+        &mut self.children
+    }
+}
+
+#[test]
+fn test_from_egg_without_display() {
+    let mut egg_egraph = egg::EGraph::<Numbered, ()>::default();
+    let leaf = egg_egraph.add(Numbered {
+        op: 0,
+        children: vec![],
+    });
+    let root = egg_egraph.add(Numbered {
+        op: 1,
+        children: vec![leaf],
+    });
+    egg_egraph.rebuild();
+
+    let egraph = EggConverter::new_with_op(&egg_egraph, |node| format!("op{}", node.op))
+        .with_roots([root])
+        .convert();
+    let root = &egraph.root_eclasses[0];
+    assert_eq!(egraph[&egraph[root].nodes[0]].op, "op1");
+    assert_eq!(egraph.validate(), Ok(()));
+}
@@ -0,0 +1,70 @@
+use egraph_serialize::*;
+
+mod common;
+use common::{class_data, node};
+
+fn typed(egraph: &mut EGraph, class: &str, typ: &str) {
+    egraph.class_data.insert(class.into(), class_data(typ, &[]));
+}
+
+#[test]
+fn test_to_egglog() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("two", node("2", &[], "I"));
+    egraph.add_node("num", node("Num", &["two"], "N"));
+    egraph.add_node("plus", node("+", &["num", "num"], "P"));
+    egraph.add_node("times", node("*", &["num", "two-again"], "P"));
+    egraph.add_node("two-again", node("Num", &["two"], "N"));
+    egraph.add_node("len", node("len", &["plus"], "I"));
+    egraph.add_node("fact", node("fact", &["two"], "U"));
+    egraph.add_node("unit", node("()", &[], "U"));
+    egraph.nodes_mut()[&NodeId::from("times")].subsumed = true;
+    typed(&mut egraph, "I", "i64");
+    typed(&mut egraph, "U", "Unit");
+    typed(&mut egraph, "P", "Math");
+
+    let expected = "\
+(sort Expr)
+(sort Math)
+(constructor Num (i64) Expr)
+(constructor op_plus (Expr Expr) Math)
+(constructor op_times (Expr Expr) Math)
+(function len (Math) i64 :no-merge)
+(relation fact (i64))
+(let c0 (Num 2))
+(let c1 (op_plus c0 c0))
+(union c1 (op_times c0 c0))
+(subsume (op_times c0 c0))
+(union c0 (Num 2))
+(set (len c1) 2)
+(fact 2)
+";
+    assert_eq!(egraph.to_egglog().unwrap(), expected);
+}
+
+#[test]
+fn test_to_egglog_names() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("let", &[], "A"));
+    egraph.add_node("b", node("c0", &[], "B"));
+    egraph.add_node("c", node("f", &[], "A"));
+    egraph.add_node("d", node("f", &["a"], "B"));
+    let program = egraph.to_egglog().unwrap();
+    assert!(program.contains("(constructor op_let () Expr)"));
+    assert!(program.contains("(constructor c0_2 () Expr)"));
+    assert!(program.contains("(constructor f () Expr)"));
+    assert!(program.contains("(constructor f_2 (Expr) Expr)"));
+}
+
+#[test]
+fn test_to_egglog_infinite_class() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("f", node("f", &["a", "f"], "F"));
+    assert_eq!(egraph.to_egglog(), Err(Error::MissingChoice("F".into())));
+
+    let mut egraph = EGraph::default();
+    egraph.add_node("f", node("f", &["f"], "I"));
+    typed(&mut egraph, "I", "i64");
+    assert_eq!(egraph.to_egglog(), Err(Error::MissingChoice("I".into())));
+}
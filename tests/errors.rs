@@ -0,0 +1,71 @@
+use egraph_serialize::*;
+
+mod common;
+use common::node;
+
+#[test]
+fn test_fallible_accessors() {
+    let mut egraph = EGraph::default();
+    egraph.try_add_node("a", node("a", &[], "A")).unwrap();
+    assert_eq!(
+        egraph.try_add_node("a", node("b", &[], "A")),
+        Err(Error::DuplicateNode("a".into()))
+    );
+    assert_eq!(egraph.get_node(&"a".into()).unwrap().op, "a");
+    assert_eq!(
+        egraph.get_node(&"b".into()),
+        Err(Error::MissingNode("b".into()))
+    );
+    assert_eq!(egraph.get_class(&"A".into()).unwrap().nodes.len(), 1);
+    assert_eq!(
+        egraph.get_class(&"B".into()),
+        Err(Error::MissingClass("B".into()))
+    );
+}
+
+#[test]
+fn test_try_split_classes() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("one", node("1", &[], "A"));
+    egraph.add_node("uno", node("1", &[], "A"));
+    egraph.add_node("f", node("f", &["one"], "F"));
+    let before = egraph.clone();
+    assert_eq!(
+        egraph.try_split_classes(|_, node| node.op == "1"),
+        Err(Error::MultipleSplitNodes {
+            class: "A".into(),
+            nodes: vec!["one".into(), "uno".into()],
+        })
+    );
+    assert_eq!(egraph, before);
+
+    egraph.add_node("g", node("g", &["missing"], "G"));
+    assert_eq!(
+        egraph.try_split_classes(|_, _| false),
+        Err(Error::MissingNode("missing".into()))
+    );
+}
+
+#[test]
+fn test_error_display() {
+    assert_eq!(
+        Error::MissingNode("b".into()).to_string(),
+        "no node with id b"
+    );
+    let error = Error::MultipleSplitNodes {
+        class: "A".into(),
+        nodes: vec!["a".into(), "b".into()],
+    };
+    assert_eq!(
+        error.to_string(),
+        "multiple nodes in e-class A should be split: a b"
+    );
+    let error = Error::TooLarge {
+        what: "children",
+        count: 1 << 32,
+    };
+    assert_eq!(
+        error.to_string(),
+        "4294967296 children are too many for u32 indices"
+    );
+}
@@ -0,0 +1,124 @@
+use egraph_serialize::*;
+
+mod common;
+use common::node_with_cost;
+
+#[test]
+fn test_extract_bottom_up() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x", node_with_cost("x", &[], "X", 1.0));
+    egraph.add_node("f", node_with_cost("f", &["x"], "F", 1.0));
+    egraph.add_node("g", node_with_cost("g", &[], "F", 5.0));
+    egraph.add_node("h", node_with_cost("h", &["r"], "F", 0.0));
+    egraph.add_node("r", node_with_cost("r", &["f", "g"], "R", 1.0));
+    egraph.root_eclasses = vec!["R".into()];
+
+    let result = egraph.extract_bottom_up();
+    assert_eq!(result.check(&egraph), Ok(()));
+    assert_eq!(result.choices[&ClassId::from("F")], NodeId::from("f"));
+    assert_eq!(result.choices[&ClassId::from("R")], NodeId::from("r"));
+    assert_eq!(
+        result.tree_cost(&egraph, &egraph.root_eclasses),
+        Cost::new(5.0).unwrap()
+    );
+    assert_eq!(
+        result.dag_cost(&egraph, &egraph.root_eclasses),
+        Cost::new(3.0).unwrap()
+    );
+
+    // Choosing `h` for `F` makes the choices cyclic
+    let mut cyclic = result.clone();
+    cyclic.choose("F".into(), "h".into());
+    assert!(cyclic
+        .tree_cost(&egraph, &egraph.root_eclasses)
+        .is_infinite());
+    assert_eq!(
+        cyclic.dag_cost(&egraph, &egraph.root_eclasses),
+        Cost::new(1.0).unwrap()
+    );
+}
+
+#[test]
+fn test_check_extraction_result() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("x", node_with_cost("x", &[], "X", 1.0));
+    egraph.add_node("f", node_with_cost("f", &["x"], "F", 1.0));
+    egraph.root_eclasses = vec!["F".into()];
+
+    let mut result = ExtractionResult::default();
+    result.choose("F".into(), "f".into());
+    assert_eq!(result.check(&egraph), Err(Error::MissingChoice("X".into())));
+
+    result.choose("X".into(), "f".into());
+    assert_eq!(
+        result.check(&egraph),
+        Err(Error::WrongClass {
+            class: "X".into(),
+            node: "f".into(),
+        })
+    );
+
+    result.choose("X".into(), "y".into());
+    assert_eq!(result.check(&egraph), Err(Error::MissingNode("y".into())));
+
+    result.choose("X".into(), "x".into());
+    assert_eq!(result.check(&egraph), Ok(()));
+}
+
+#[test]
+fn test_extract_fixtures() {
+    for entry in glob::glob("tests/*.json").unwrap() {
+        let egraph = EGraph::from_json_file(entry.unwrap()).unwrap();
+        let result = egraph.extract_bottom_up();
+        result.check(&egraph).unwrap();
+        let roots = &egraph.root_eclasses;
+        let tree_cost = result.tree_cost(&egraph, roots);
+        assert!(tree_cost.is_finite());
+        assert!(result.dag_cost(&egraph, roots) <= tree_cost);
+    }
+}
+
+#[test]
+fn test_extraction_result_round_trip() {
+    for entry in glob::glob("tests/*.json").unwrap() {
+        let entry = entry.unwrap();
+        let egraph = EGraph::from_json_file(&entry).unwrap();
+        let result = egraph.extract_bottom_up();
+        let path = std::env::temp_dir().join(format!(
+            "{}-extraction-{}.json",
+            entry.file_stem().unwrap().to_str().unwrap(),
+            std::process::id()
+        ));
+        result.to_json_file(&path).unwrap();
+        assert_eq!(ExtractionResult::from_json_file(&path).unwrap(), result);
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_extract_terms() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("one", node_with_cost("1", &[], "A", 1.0));
+    egraph.add_node("two", node_with_cost("2", &[], "B", 1.0));
+    egraph.add_node("g", node_with_cost("g", &["two"], "G", 1.0));
+    egraph.add_node("f", node_with_cost("f", &["one", "g", "g"], "F", 1.0));
+    egraph.add_node("loop", node_with_cost("loop", &["f"], "F", 0.0));
+    egraph.root_eclasses = vec!["F".into(), "G".into()];
+
+    let mut result = egraph.extract_bottom_up();
+    let terms = result.terms(&egraph).unwrap();
+    assert_eq!(terms[0].to_string(), "(f 1 (g 2) (g 2))");
+    assert_eq!(terms[1].to_string(), "(g 2)");
+    // The shared e-class is only built once
+    assert!(std::sync::Arc::ptr_eq(&terms[0].children[1], &terms[1]));
+
+    result.choose("F".into(), "loop".into());
+    assert_eq!(result.terms(&egraph), Err(Error::Cycle("F".into())));
+
+    egraph.inline_leaves();
+    let result = egraph.extract_bottom_up();
+    assert_eq!(
+        result.term(&egraph, &"F".into()).unwrap().to_string(),
+        "f(1, g(2), g(2))"
+    );
+}
@@ -1,4 +1,4 @@
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use egraph_serialize::*;
 
@@ -14,9 +14,215 @@ fn test_round_trip() {
     assert!(n_tested > 0);
 }
 
+#[test]
+fn test_indexed_round_trip() {
+    for entry in test_files() {
+        println!("Testing indexed {entry:?}");
+        let egraph = EGraph::from_json_file(entry.as_path()).unwrap();
+        let indexed = IndexedEGraph::new(&egraph).unwrap();
+        assert_eq!(indexed.num_nodes(), egraph.nodes.len());
+        assert_eq!(indexed.num_classes(), egraph.classes().len());
+        for node in 0..indexed.num_nodes() as u32 {
+            let original = &egraph[indexed.node_id(node)];
+            assert_eq!(indexed.op(node), original.op);
+            assert_eq!(indexed.class_id(indexed.node_class(node)), &original.eclass);
+            for &child in indexed.children(node) {
+                assert!(indexed.parents(indexed.node_class(child)).contains(&node));
+            }
+        }
+        assert_eq!(indexed.to_egraph(), egraph);
+    }
+}
+
+#[test]
+fn test_streaming_round_trip() {
+    for entry in test_files() {
+        println!("Testing {entry:?}");
+        let egraph = EGraph::from_json_file(entry.as_path()).unwrap();
+
+        let mut writer = JsonWriter::new(Vec::new()).unwrap();
+        for (node_id, node) in &egraph.nodes {
+            writer.write_node(node_id, node).unwrap();
+        }
+        let json = writer
+            .finish(&egraph.root_eclasses, &egraph.class_data)
+            .unwrap();
+
+        let mut nodes = vec![];
+        let mut streamed = EGraph::visit_json_nodes(json.as_slice(), |node_id, node| {
+            nodes.push((node_id, node));
+        })
+        .unwrap();
+        for (node_id, node) in nodes {
+            streamed.add_node(node_id, node);
+        }
+        assert_eq!(egraph, streamed);
+    }
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = EGraph::json_schema();
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    for entry in test_files() {
+        println!("Testing schema {entry:?}");
+        let json: serde_json::Value =
+            serde_json::from_reader(std::fs::File::open(&entry).unwrap()).unwrap();
+        assert!(validator.is_valid(&json));
+        let egraph: EGraph = serde_json::from_value(json).unwrap();
+        assert!(validator.is_valid(&serde_json::to_value(&egraph).unwrap()));
+    }
+    let bad = serde_json::json!({ "nodes": { "a": { "op": "a" } } });
+    assert!(!validator.is_valid(&bad));
+}
+
+#[test]
+fn test_format_version() {
+    let egraph = EGraph::from_json_file("tests/tiny.json").unwrap();
+    assert_eq!(egraph.format_version(), 0);
+
+    let json = serde_json::to_value(&egraph).unwrap();
+    assert_eq!(json["version"], FORMAT_VERSION);
+    let egraph2: EGraph = serde_json::from_value(json).unwrap();
+    assert_eq!(egraph2.format_version(), FORMAT_VERSION);
+    assert_eq!(egraph, egraph2);
+
+    let future = format!(r#"{{"version": {}, "nodes": {{}}}}"#, FORMAT_VERSION + 1);
+    let error = serde_json::from_str::<EGraph>(&future).unwrap_err();
+    assert!(error.to_string().contains("newer than the latest version"));
+    let error = EGraph::visit_json_nodes(future.as_bytes(), |_, _| {}).unwrap_err();
+    assert!(error.to_string().contains("newer than the latest version"));
+
+    // A streaming reader sees no nodes when the version comes first, but all of them when it comes last
+    let node = r#""a": {"op": "a", "children": [], "eclass": "A"}"#;
+    let version = format!(r#""version": {}"#, FORMAT_VERSION + 1);
+    for (document, expected_nodes) in [
+        (format!(r#"{{{version}, "nodes": {{{node}}}}}"#), 0),
+        (format!(r#"{{"nodes": {{{node}}}, {version}}}"#), 1),
+    ] {
+        let mut seen = 0;
+        let error = EGraph::visit_json_nodes(document.as_bytes(), |_, _| seen += 1).unwrap_err();
+        assert!(error.to_string().contains("newer than the latest version"));
+        assert_eq!(seen, expected_nodes);
+    }
+
+    // E-graphs embedded in other documents are read, and upgraded, the same way
+    #[derive(serde::Deserialize)]
+    struct Wrapper {
+        egraph: EGraph,
+    }
+    let legacy = std::fs::read_to_string("tests/tiny.json").unwrap();
+    let wrapper: Wrapper = serde_json::from_str(&format!(r#"{{"egraph": {legacy}}}"#)).unwrap();
+    assert_eq!(wrapper.egraph.format_version(), 0);
+    assert_eq!(wrapper.egraph, egraph);
+    let error = serde_json::from_str::<Wrapper>(&format!(r#"{{"egraph": {future}}}"#));
+    assert!(error.is_err());
+}
+
+#[cfg(all(feature = "gzip", feature = "zstd"))]
+#[test]
+fn test_compressed_round_trip() {
+    let fixtures = [
+        (
+            "tests/compressed/math_powers.json.gz",
+            "tests/math_powers.json",
+        ),
+        (
+            "tests/compressed/lambda_if.json.zst",
+            "tests/lambda_if.json",
+        ),
+    ];
+    for (compressed, plain) in fixtures {
+        println!("Testing {compressed:?}");
+        let egraph = EGraph::from_json_file(compressed).unwrap();
+        assert_eq!(egraph, EGraph::from_json_file(plain).unwrap());
+    }
+
+    for entry in test_files() {
+        println!("Testing compressed {entry:?}");
+        let egraph = EGraph::from_json_file(entry.as_path()).unwrap();
+        let stem = entry.file_stem().unwrap().to_str().unwrap();
+        for extension in ["json.gz", "json.zst"] {
+            let path =
+                std::env::temp_dir().join(format!("{stem}-{}.{extension}", std::process::id()));
+            egraph.to_json_file(&path).unwrap();
+            assert_eq!(egraph, EGraph::from_json_file(&path).unwrap());
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn test_compressed_needs_feature() {
+    let error = EGraph::from_json_file("tests/compressed/math_powers.json.gz").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn test_compressed_needs_feature_keeps_file() {
+    let path = std::env::temp_dir().join(format!("keep-{}.json.gz", std::process::id()));
+    std::fs::write(&path, "existing").unwrap();
+    let error = EGraph::default().to_json_file(&path).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "existing");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn test_petgraph_round_trip() {
+    for entry in test_files() {
+        println!("Testing petgraph {entry:?}");
+        let egraph = EGraph::from_json_file(entry.as_path()).unwrap();
+        let graph = egraph.to_petgraph();
+        assert!(graph.node_count() >= egraph.nodes.len() + egraph.classes().len());
+        assert_eq!(EGraph::from_petgraph(&graph).unwrap(), egraph);
+    }
+
+    let egraph = EGraph::from_json_file("tests/tiny.json").unwrap();
+    let mut graph = egraph.to_petgraph();
+    graph.clear_edges();
+    assert_eq!(
+        EGraph::from_petgraph(&graph),
+        Err(Error::NodeClass("node1".into()))
+    );
+
+    // Repeated roots and missing children survive the round trip
+    let mut egraph = EGraph::from_sexps("(f a) b").unwrap();
+    egraph.root_eclasses = vec!["1".into(), "2".into(), "1".into()];
+    egraph.nodes_mut()[&NodeId::from("1")]
+        .children
+        .push("missing".into());
+    let graph = egraph.to_petgraph();
+    assert!(graph.node_weights().any(|vertex| *vertex
+        == PetgraphNode::Missing {
+            id: "missing".into()
+        }));
+    let back = EGraph::from_petgraph(&graph).unwrap();
+    assert_eq!(back, egraph);
+    assert_eq!(back.root_eclasses, egraph.root_eclasses);
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_binary_round_trip() {
+    let mut n_tested = 0;
+    for entry in test_files() {
+        println!("Testing {entry:?}");
+        let egraph = EGraph::from_json_file(entry.as_path()).unwrap();
+        egraph.test_binary_round_trip();
+        n_tested += 1;
+    }
+    assert!(n_tested > 0);
+}
+
 #[cfg(feature = "graphviz")]
 #[test]
 fn test_graphviz() {
+    use std::path::Path;
+
     // Check if `dot` command is available
     let no_dot = std::process::Command::new("dot")
         .arg("-V")
@@ -0,0 +1,71 @@
+use egraph_serialize::*;
+
+#[test]
+fn test_from_sexps() {
+    let egraph = EGraph::from_sexps(
+        "
+        ; x + 0 = x
+        (+ x 0)
+        (= (+ x 0) x)
+        (* x x)
+        ",
+    )
+    .unwrap();
+    assert_eq!(egraph.nodes.len(), 4);
+    let plus = &egraph[&NodeId::from("2")];
+    assert_eq!(plus.op, "+");
+    assert_eq!(plus.children, vec!["0".into(), "1".into()]);
+    assert_eq!(plus.eclass, "0".into());
+    assert_eq!(
+        egraph[&NodeId::from("3")].children,
+        vec!["0".into(), "0".into()]
+    );
+    assert_eq!(egraph.root_eclasses, vec!["0".into(), "3".into()]);
+    assert_eq!(egraph.classes().len(), 3);
+    assert!(egraph.validate().is_ok());
+}
+
+#[test]
+fn test_from_sexps_atoms() {
+    let egraph = EGraph::from_sexps(r#"(f "a b" "c\"d" x) x"#).unwrap();
+    let ops: Vec<&str> = egraph.nodes.values().map(|node| node.op.as_str()).collect();
+    assert_eq!(ops, [r#""a b""#, r#""c\"d""#, "x", "f"]);
+    assert_eq!(egraph.root_eclasses, vec!["3".into(), "2".into()]);
+}
+
+#[test]
+fn test_sexp_builder() {
+    let mut builder = SexpBuilder::new();
+    let a = builder.add("(f a)").unwrap();
+    let b = builder.add("(g (f a))").unwrap();
+    assert_eq!(builder.add("(f a)").unwrap(), a);
+    builder.union(&b, &a).unwrap();
+    builder.add_root(&b).unwrap();
+    assert_eq!(
+        builder.union(&a, &"7".into()),
+        Err(Error::MissingNode("7".into()))
+    );
+    assert!(builder.add("a b").is_err());
+
+    let egraph = builder.build();
+    assert_eq!(egraph.nodes.len(), 3);
+    assert_eq!(egraph[&b].eclass, "1".into());
+    assert_eq!(egraph.root_eclasses, vec!["1".into()]);
+}
+
+#[test]
+fn test_from_sexps_errors() {
+    let error = |input: &str| match EGraph::from_sexps(input) {
+        Err(Error::Parse { position, message }) => (position, message),
+        other => panic!("expected a parse error, got {other:?}"),
+    };
+    assert_eq!(error("(f x))"), (5, "unexpected `)`".to_string()));
+    assert_eq!(error("x (f (g x)"), (2, "unclosed `(`".to_string()));
+    assert_eq!(error("(f \"x)"), (3, "unterminated string".to_string()));
+    assert_eq!(error("(f ())"), (3, "empty list".to_string()));
+    assert_eq!(error("((f) x)"), (1, "expected an op".to_string()));
+    assert_eq!(
+        error("(= x)"),
+        (0, "`=` needs at least two terms".to_string())
+    );
+}
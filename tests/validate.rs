@@ -0,0 +1,56 @@
+use egraph_serialize::*;
+
+mod common;
+use common::node;
+
+#[test]
+fn test_fixtures_have_no_dangling_children() {
+    for entry in glob::glob("tests/*.json").unwrap() {
+        let egraph = EGraph::from_json_file(entry.unwrap()).unwrap();
+        let errors = egraph.validate().err().unwrap_or_default();
+        assert!(!errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::DanglingChild { .. })));
+    }
+    let tiny = EGraph::from_json_file("tests/tiny.json").unwrap();
+    assert_eq!(tiny.validate(), Ok(()));
+}
+
+#[test]
+fn test_validate_reports_problems() {
+    let mut egraph = EGraph::default();
+    egraph.add_node("a", node("a", &[], "A"));
+    egraph.add_node("f", node("f", &["a", "missing"], "F"));
+    egraph.add_node("g", node("g", &[], "G"));
+    egraph.nodes[&NodeId::from("a")].cost = Cost::new(-1.0).unwrap();
+    egraph.root_eclasses = vec!["F".into(), "F".into(), "X".into()];
+    egraph.class_data.insert(
+        "Y".into(),
+        ClassData {
+            typ: None,
+            extra: Default::default(),
+        },
+    );
+
+    assert_eq!(
+        egraph.validate(),
+        Err(vec![
+            ValidationError::NegativeCost {
+                node: "a".into(),
+                cost: Cost::new(-1.0).unwrap(),
+            },
+            ValidationError::DanglingChild {
+                node: "f".into(),
+                position: 1,
+                child: "missing".into(),
+            },
+            ValidationError::IsolatedNode {
+                node: "g".into(),
+                class: "G".into(),
+            },
+            ValidationError::DuplicateRootClass("F".into()),
+            ValidationError::MissingRootClass("X".into()),
+            ValidationError::OrphanClassData("Y".into()),
+        ])
+    );
+}